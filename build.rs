@@ -5,7 +5,6 @@ use {
         fs,
         path::PathBuf,
     },
-    fs_extra,
     winresource::WindowsResource,
 };
 
@@ -62,7 +61,7 @@ fn main() -> io::Result<()> {
     fs_extra::dir::copy(&resources_src, &resources_dest, &options)
          .map_err(|e| {
             eprintln!("Build Error: Error copying resources: {}", e);
-            io::Error::new(io::ErrorKind::Other, format!("Failed to copy resources: {}", e))
+            io::Error::other(format!("Failed to copy resources: {}", e))
         })?;
 
     println!(
@@ -0,0 +1,345 @@
+use crate::constants::*;
+use crate::simulation::Simulation;
+use crate::types::*;
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// How a computer snake picks its next turn
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum AiSkill {
+    Greedy,     // Heads straight for the nearest food, only looking one cell ahead
+    Safe,       // Takes the shortest path to food that leaves it room, else follows its tail
+    Aggressive, // Plays safe, but cuts in front of the player whenever it gets there first
+}
+
+impl AiSkill {
+    pub const ALL: [AiSkill; 3] = [AiSkill::Greedy, AiSkill::Safe, AiSkill::Aggressive];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AiSkill::Greedy => "Greedy",
+            AiSkill::Safe => "Safe",
+            AiSkill::Aggressive => "Aggressive",
+        }
+    }
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+// The turn snake `index` makes this tick. None keeps it going straight, which only
+// happens when every way is blocked.
+pub fn choose_direction(sim: &Simulation, index: usize, skill: AiSkill) -> Option<Direction> {
+    if !sim.snakes[index].alive {
+        return None;
+    }
+    match skill {
+        AiSkill::Greedy => greedy(sim, index),
+        AiSkill::Safe => safe(sim, index),
+        AiSkill::Aggressive => cut_off(sim, index).or_else(|| safe(sim, index)),
+    }
+}
+
+// Plays snake `index` well enough to fill the board. On a board without walls that has
+// a Hamiltonian cycle it follows the cycle, cutting ahead towards the food only where the
+// cut can't reach its own tail, so it never traps itself. Anywhere else it plays Safe.
+pub fn autopilot(sim: &Simulation, index: usize) -> Option<Direction> {
+    if !sim.snakes[index].alive {
+        return None;
+    }
+    match hamiltonian_cycle(sim) {
+        Some(cycle) => follow_cycle(sim, index, &cycle).or_else(|| safe(sim, index)),
+        None => safe(sim, index),
+    }
+}
+
+// Each cell's place along a cycle through every cell, row major. Rows are swept back
+// and forth with column 0 left free as the way back to the top; boards with an odd
+// number of rows are swept by columns instead. None on boards with walls, and on
+// boards with an odd number of cells, which have no such cycle.
+fn hamiltonian_cycle(sim: &Simulation) -> Option<Vec<usize>> {
+    if sim.wall_cells().next().is_some() {
+        return None;
+    }
+    let (width, height) = (sim.width as usize, sim.height as usize);
+    let transposed = height % 2 == 1;
+    if transposed && width % 2 == 1 {
+        return None;
+    }
+    // Sweep an `across` by `down` board, then map it onto the real one
+    let (across, down) = if transposed {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    let mut path = Vec::with_capacity(width * height);
+    for row in 0..down {
+        if row % 2 == 0 {
+            path.extend((1..across).map(|column| (column, row)));
+        } else {
+            path.extend((1..across).rev().map(|column| (column, row)));
+        }
+    }
+    path.extend((0..down).rev().map(|row| (0, row)));
+
+    let mut cycle = vec![0; width * height];
+    for (place, (column, row)) in path.into_iter().enumerate() {
+        let (x, y) = if transposed {
+            (row, column)
+        } else {
+            (column, row)
+        };
+        cycle[y * width + x] = place;
+    }
+    Some(cycle)
+}
+
+fn follow_cycle(sim: &Simulation, index: usize, cycle: &[usize]) -> Option<Direction> {
+    let snake = &sim.snakes[index];
+    let cells = cycle.len();
+    let place = |pos: na::Point2<i32>| cycle[cell_index(sim, pos)];
+    let ahead = |from: usize, to: usize| (to + cells - from) % cells;
+
+    // Cutting ahead is only safe while the body lies along the cycle in order, so the
+    // stretch from the head round to the tail is empty
+    let head = place(snake.head());
+    let tail = place(snake.body[snake.body.len() - 1].pos);
+    let in_order = snake
+        .body
+        .windows(2)
+        .all(|pair| ahead(tail, place(pair[1].pos)) < ahead(tail, place(pair[0].pos)));
+    if !in_order {
+        return None;
+    }
+    let empty_ahead = if snake.body.len() > 1 {
+        ahead(head, tail)
+    } else {
+        cells
+    };
+    let food = ahead(head, place(sim.food.pos));
+    // Once the snake is long the cuts stop, the cycle alone fills the rest
+    let may_cut = snake.body.len() * 2 < cells;
+
+    moves(sim, index)
+        .map(|(direction, cell)| (direction, ahead(head, place(cell))))
+        .filter(|&(_, steps)| {
+            steps == 1 || (may_cut && steps <= food && steps + CYCLE_CUT_MARGIN < empty_ahead)
+        })
+        .max_by_key(|&(_, steps)| steps)
+        .map(|(direction, _)| direction)
+}
+
+// The open neighbour closest to any food, by straight line distance
+fn greedy(sim: &Simulation, index: usize) -> Option<Direction> {
+    let foods = food_cells(sim);
+    moves(sim, index)
+        .min_by_key(|&(_, cell)| nearest_food(sim, &foods, cell))
+        .map(|(direction, _)| direction)
+}
+
+// The shortest path to food, as long as the snake still fits in the space it leads
+// into. Failing that it keeps its own tail in reach, since that space keeps opening
+// up as it moves, drifting towards the food so it doesn't circle forever. As a last
+// resort it takes the move with the most room.
+fn safe(sim: &Simulation, index: usize) -> Option<Direction> {
+    let snake = &sim.snakes[index];
+    let length = snake.body.len();
+    let foods = food_cells(sim);
+    if let Some((direction, _)) = path_to(sim, index, |cell| foods.contains(&cell)) {
+        if let Some(next) = neighbour(sim, snake.head(), direction) {
+            if !contested(sim, index, next) && room(sim, next, length + 1) > length {
+                return Some(direction);
+            }
+        }
+    }
+
+    if length > 1 {
+        let tail = snake.body[length - 1].pos;
+        let towards_food = moves(sim, index)
+            .filter(|&(_, cell)| reaches(sim, cell, tail))
+            .min_by_key(|&(_, cell)| nearest_food(sim, &foods, cell));
+        if let Some((direction, _)) = towards_food {
+            return Some(direction);
+        }
+    }
+
+    let board = (sim.width * sim.height) as usize;
+    moves(sim, index)
+        .max_by_key(|&(_, cell)| (!contested(sim, index, cell), room(sim, cell, board)))
+        .map(|(direction, _)| direction)
+}
+
+// Aims for a cell just ahead of the player when it can get there before the player
+// does, so the player has to turn away or run into it
+fn cut_off(sim: &Simulation, index: usize) -> Option<Direction> {
+    let player = sim.player();
+    if index == 0 || !player.alive {
+        return None;
+    }
+    let mut target = player.head();
+    for _ in 0..CUT_OFF_DISTANCE {
+        target = neighbour(sim, target, player.direction)?;
+    }
+    if !is_open(sim, target) {
+        return None;
+    }
+    let (direction, steps) = path_to(sim, index, |cell| cell == target)?;
+    let length = sim.snakes[index].body.len();
+    let next = neighbour(sim, sim.snakes[index].head(), direction)?;
+    (steps < CUT_OFF_DISTANCE && room(sim, next, length + 1) > length).then_some(direction)
+}
+
+// The first turn of the shortest path over open cells to a cell matching `goal`,
+// and how many steps that path takes
+fn path_to(
+    sim: &Simulation,
+    index: usize,
+    goal: impl Fn(na::Point2<i32>) -> bool,
+) -> Option<(Direction, usize)> {
+    let mut seen = vec![false; (sim.width * sim.height) as usize];
+    let mut queue = VecDeque::new();
+    for (direction, cell) in moves(sim, index) {
+        seen[cell_index(sim, cell)] = true;
+        queue.push_back((cell, direction, 1));
+    }
+    while let Some((cell, first, steps)) = queue.pop_front() {
+        if goal(cell) {
+            return Some((first, steps));
+        }
+        for direction in DIRECTIONS {
+            let Some(next) = neighbour(sim, cell, direction) else {
+                continue;
+            };
+            let next_index = cell_index(sim, next);
+            if !seen[next_index] && is_open(sim, next) {
+                seen[next_index] = true;
+                queue.push_back((next, first, steps + 1));
+            }
+        }
+    }
+    None
+}
+
+// How many open cells can be reached from `start`, counting stops once it hits `limit`
+fn room(sim: &Simulation, start: na::Point2<i32>, limit: usize) -> usize {
+    let mut count = 0;
+    flood(sim, start, |_| {
+        count += 1;
+        count < limit
+    });
+    count
+}
+
+// True when `target` can be reached from `start` over open cells
+fn reaches(sim: &Simulation, start: na::Point2<i32>, target: na::Point2<i32>) -> bool {
+    let mut found = false;
+    flood(sim, start, |cell| {
+        found = cell == target;
+        !found
+    });
+    found
+}
+
+// Visits every open cell reachable from `start` until `visit` returns false
+fn flood(sim: &Simulation, start: na::Point2<i32>, mut visit: impl FnMut(na::Point2<i32>) -> bool) {
+    let mut seen = vec![false; (sim.width * sim.height) as usize];
+    let mut stack = vec![start];
+    seen[cell_index(sim, start)] = true;
+    while let Some(cell) = stack.pop() {
+        if !visit(cell) {
+            return;
+        }
+        for direction in DIRECTIONS {
+            let Some(next) = neighbour(sim, cell, direction) else {
+                continue;
+            };
+            let next_index = cell_index(sim, next);
+            if !seen[next_index] && is_open(sim, next) {
+                seen[next_index] = true;
+                stack.push(next);
+            }
+        }
+    }
+}
+
+// Every open cell next to the head, the way back into the neck left out
+fn moves(
+    sim: &Simulation,
+    index: usize,
+) -> impl Iterator<Item = (Direction, na::Point2<i32>)> + '_ {
+    let snake = &sim.snakes[index];
+    DIRECTIONS
+        .into_iter()
+        .filter(move |&direction| direction != snake.direction.opposite())
+        .filter_map(move |direction| {
+            neighbour(sim, snake.head(), direction).map(|cell| (direction, cell))
+        })
+        .filter(move |&(_, cell)| is_open(sim, cell))
+}
+
+// The cell one step away, around the edge when the walls wrap. None off a solid edge.
+fn neighbour(
+    sim: &Simulation,
+    pos: na::Point2<i32>,
+    direction: Direction,
+) -> Option<na::Point2<i32>> {
+    let mut next = pos + direction.delta();
+    if sim.wall_rule == WallRule::Wrap {
+        next.x = next.x.rem_euclid(sim.width);
+        next.y = next.y.rem_euclid(sim.height);
+    }
+    (!sim.is_outside(next)).then_some(next)
+}
+
+// A cell a snake can move into next tick. Tails count as open since they move on.
+fn is_open(sim: &Simulation, pos: na::Point2<i32>) -> bool {
+    if sim.is_wall(pos) {
+        return false;
+    }
+    if !sim.is_occupied(pos) {
+        return true;
+    }
+    sim.snakes.iter().any(|snake| {
+        snake.alive && snake.body.len() > 1 && snake.body[snake.body.len() - 1].pos == pos
+    })
+}
+
+// True when another snake's head could move into `cell` on the same tick
+fn contested(sim: &Simulation, index: usize, cell: na::Point2<i32>) -> bool {
+    sim.snakes.iter().enumerate().any(|(other, snake)| {
+        other != index && snake.alive && distance(sim, snake.head(), cell) == 1
+    })
+}
+
+fn nearest_food(sim: &Simulation, foods: &[na::Point2<i32>], cell: na::Point2<i32>) -> i32 {
+    foods
+        .iter()
+        .map(|&food| distance(sim, cell, food))
+        .min()
+        .unwrap_or(0)
+}
+
+fn food_cells(sim: &Simulation) -> Vec<na::Point2<i32>> {
+    let mut cells = vec![sim.food.pos];
+    cells.extend(sim.pellets.iter().map(|pellet| pellet.pos));
+    cells
+}
+
+// Steps between two cells on an empty board, shorter around the edge when it wraps
+fn distance(sim: &Simulation, a: na::Point2<i32>, b: na::Point2<i32>) -> i32 {
+    let (mut dx, mut dy) = ((a.x - b.x).abs(), (a.y - b.y).abs());
+    if sim.wall_rule == WallRule::Wrap {
+        dx = dx.min(sim.width - dx);
+        dy = dy.min(sim.height - dy);
+    }
+    dx + dy
+}
+
+fn cell_index(sim: &Simulation, pos: na::Point2<i32>) -> usize {
+    (pos.y * sim.width + pos.x) as usize
+}
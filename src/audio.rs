@@ -0,0 +1,143 @@
+use crate::constants::*;
+use ggez::{
+    audio::{self, SoundSource, Source},
+    Context,
+    GameResult,
+};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum Music {
+    Menu,
+    Game,
+    Special,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum Sfx {
+    Eat,
+    GameOver,
+    MenuChange,
+    BoardCleared,
+}
+
+// Owns every sound in the game and the volume channels they are mixed through
+pub struct AudioManager {
+    menu_music: Source,
+    game_music: Source,
+    special_mode_music: Source,
+    eat_sound: Source,
+    game_over_sound: Source,
+    menu_change_sound: Source,
+    board_cleared_sound: Source,
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub muted: bool,
+    ducked: bool, // Music drops to PAUSED_MUSIC_FACTOR while paused
+}
+
+impl AudioManager {
+    pub fn new(ctx: &mut Context, resource_prefix: &str) -> GameResult<AudioManager> {
+        let load = |ctx: &mut Context, file: &str| {
+            audio::Source::new(ctx, format!("{}/{}", resource_prefix, file))
+        };
+        let mut menu_music = load(ctx, "menu_music.mp3")?;
+        let mut game_music = load(ctx, "game_music.wav")?;
+        let mut special_mode_music = load(ctx, "special_mode_music.mp3")?;
+
+        // Set music to loop
+        menu_music.set_repeat(true);
+        game_music.set_repeat(true);
+        special_mode_music.set_repeat(true);
+
+        let mut manager = AudioManager {
+            menu_music,
+            game_music,
+            special_mode_music,
+            eat_sound: load(ctx, "eat.ogg")?,
+            game_over_sound: load(ctx, "game_over.wav")?,
+            menu_change_sound: load(ctx, "menu_option_change.wav")?,
+            board_cleared_sound: load(ctx, "board_cleared.wav")?,
+            master_volume: 1.0,
+            music_volume: INITIAL_MUSIC_VOLUME,
+            sfx_volume: INITIAL_MUSIC_VOLUME,
+            muted: false,
+            ducked: false,
+        };
+        manager.apply_volumes();
+        Ok(manager)
+    }
+
+    // Pushes the channel volumes down to every source
+    pub fn apply_volumes(&mut self) {
+        let master = if self.muted { 0.0 } else { self.master_volume };
+        let duck = if self.ducked { PAUSED_MUSIC_FACTOR } else { 1.0 };
+        let music = master * self.music_volume * duck;
+        let sfx = master * self.sfx_volume;
+
+        self.menu_music.set_volume(music);
+        self.game_music.set_volume(music);
+        self.special_mode_music.set_volume(music);
+        self.eat_sound.set_volume(sfx);
+        self.game_over_sound.set_volume(sfx);
+        self.menu_change_sound.set_volume(sfx);
+        self.board_cleared_sound.set_volume(sfx);
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.apply_volumes();
+    }
+
+    pub fn set_ducked(&mut self, ducked: bool) {
+        self.ducked = ducked;
+        self.apply_volumes();
+    }
+
+    pub fn play_sfx(&mut self, ctx: &mut Context, sfx: Sfx) -> GameResult {
+        let source = match sfx {
+            Sfx::Eat => &mut self.eat_sound,
+            Sfx::GameOver => &mut self.game_over_sound,
+            Sfx::MenuChange => &mut self.menu_change_sound,
+            Sfx::BoardCleared => &mut self.board_cleared_sound,
+        };
+        // Stop the sound first to allow retriggering if pressed quickly
+        source.stop(ctx)?;
+        source.play(ctx)
+    }
+
+    // Keeps exactly one music track running, starting it if it isn't already
+    pub fn play_music(&mut self, ctx: &mut Context, music: Music) -> GameResult {
+        if music != Music::Menu && self.menu_music.playing() {
+            self.menu_music.stop(ctx)?;
+        }
+        if music != Music::Game && self.game_music.playing() {
+            self.game_music.set_pitch(1.0); // Reset pitch before stopping
+            self.game_music.stop(ctx)?;
+        }
+        if music != Music::Special && self.special_mode_music.playing() {
+            self.special_mode_music.stop(ctx)?;
+        }
+
+        let source = match music {
+            Music::Menu => &mut self.menu_music,
+            Music::Game => &mut self.game_music,
+            Music::Special => &mut self.special_mode_music,
+        };
+        if !source.playing() {
+            source.play(ctx)?;
+        }
+        Ok(())
+    }
+
+    pub fn stop_music(&mut self, ctx: &mut Context) -> GameResult {
+        self.game_music.set_pitch(1.0); // Reset pitch
+        self.game_music.stop(ctx)?;
+        self.special_mode_music.stop(ctx)?;
+        self.menu_music.stop(ctx)
+    }
+
+    pub fn set_game_music_pitch(&mut self, pitch: f32) {
+        self.game_music.set_pitch(pitch);
+    }
+}
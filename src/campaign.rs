@@ -0,0 +1,42 @@
+use crate::level::Level;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// Best star rating per campaign level, keyed by level name. A level with an
+// entry has been completed, which is also what unlocks the one after it.
+#[derive(Serialize, Deserialize, Default)]
+pub struct CampaignProgress {
+    pub stars: BTreeMap<String, u8>,
+}
+
+impl CampaignProgress {
+    pub fn load(path: &Path) -> CampaignProgress {
+        storage::load_json(path).unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) {
+        storage::save_json(path, self);
+    }
+
+    pub fn stars(&self, level: &str) -> u8 {
+        self.stars.get(level).copied().unwrap_or(0)
+    }
+
+    // Keeps the better of the old and new rating, true when the new one is better
+    pub fn record(&mut self, level: &str, stars: u8) -> bool {
+        let best = self.stars.entry(level.to_string()).or_insert(0);
+        if stars > *best {
+            *best = stars;
+            true
+        } else {
+            false
+        }
+    }
+
+    // The first level is always open, every other one once the level before it is done
+    pub fn is_unlocked(&self, levels: &[Level], index: usize) -> bool {
+        index == 0 || self.stars(&levels[index - 1].name) > 0
+    }
+}
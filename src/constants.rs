@@ -1,9 +1,18 @@
+// Where ggez keeps the user data dir, and the folder of ours inside it
+pub const GAME_ID: &str = "Rust Snake Game";
+pub const GAME_AUTHOR: &str = "Jacob Mish";
+pub const DATA_DIR_NAME: &str = "RustSnakeGame";
+
 pub const REFERENCE_WIDTH: f32 = 1400.0;
 pub const REFERENCE_HEIGHT: f32 = 1050.0;
 pub const MIN_WINDOW_WIDTH: f32 = 800.0;
 pub const MIN_WINDOW_HEIGHT: f32 = 600.0;
 pub const REFERENCE_SNAKE_SIZE: f32 = 50.0;
 
+// Board size in cells, 28x21 at the reference window size.
+pub const GRID_WIDTH: i32 = (REFERENCE_WIDTH / REFERENCE_SNAKE_SIZE) as i32;
+pub const GRID_HEIGHT: i32 = (REFERENCE_HEIGHT / REFERENCE_SNAKE_SIZE) as i32;
+
 // Controls snake speed.
 pub const EASY_MOVE_TIME: f32 = 0.12;
 pub const NORMAL_MOVE_TIME: f32 = 0.08;
@@ -14,8 +23,61 @@ pub const SPECIAL_START_MOVE_TIME: f32 = 0.15; // Initial slower speed for Speci
 pub const SPEED_UP_FACTOR: f32 = 0.95; // Multiplier for the speed increase
 pub const MIN_MOVE_TIME: f32 = 0.03; // Minimum move time to avoid it being too fast
 
-// Helps remove floating point errors
-pub const EPSILON: f32 = 0.01; 
+// Turns a player can press ahead of the snake, one is taken each tick
+pub const INPUT_QUEUE_LENGTH: usize = 3; // The default, the settings screen goes up to the max
+pub const MAX_INPUT_QUEUE_LENGTH: usize = 5;
+
+// Share of a full tilt the left stick needs before it counts as pushed
+pub const STICK_DEADZONE: f32 = 0.5;
+
+// Extra points for filling every cell of the board
+pub const BOARD_CLEAR_BONUS: i32 = 100;
+
+// Level constants
+pub const OPEN_BOARD_NAME: &str = "Open Board"; // The built in empty board, always listed first
+pub const MIN_LEVEL_SIZE: i32 = 5; // Smallest and largest side a level file may declare
+pub const MAX_LEVEL_SIZE: i32 = 60;
+pub const EDITOR_DEFAULT_NAME: &str = "Untitled";
+pub const EDITOR_NAME_LENGTH: usize = 24; // Longest level name the editor accepts
+pub const EDITOR_BOARD_FRACTION: f32 = 0.78; // Share of the boundary height the edited board gets
+
+// Versus constants
+pub const MAX_PLAYERS: usize = 2; // Players sharing the keyboard
+pub const MATCH_LENGTHS: [u32; 4] = [1, 3, 5, 7]; // Rounds a versus match can be the best of
+pub const COOP_RESPAWN_DELAY: f32 = 3.0; // Seconds a co-op snake sits out when respawns are on
+
+// AI constants
+pub const MAX_RIVALS: usize = 3; // Computer snakes that can join the player
+pub const PELLET_LIFETIME: f32 = 8.0; // Seconds the food left by a fallen snake stays around
+pub const CUT_OFF_DISTANCE: usize = 3; // Cells ahead of the player an aggressive snake aims for
+pub const CYCLE_CUT_MARGIN: usize = 4; // Cells the autopilot keeps between a shortcut and its tail
+
+// Replay constants
+pub const MAX_REPLAYS: usize = 50; // Recorded runs kept, the oldest are deleted first
+pub const REPLAY_SPEEDS: [f32; 4] = [0.5, 1.0, 2.0, 4.0];
+pub const REPLAY_LIST_ROWS: usize = 12; // Replays shown at once on the Replays screen
+
+// Attract mode constants
+pub const DEMO_IDLE_TIME: f32 = 15.0; // Seconds on the menu without input before the demo starts
+
+// Menu constants
+pub const MENU_FONT_SIZE: f32 = 44.0; // Rows of the main menu, before window scaling
+pub const MENU_ROW_SPACING: f32 = 1.3; // Row pitch as a multiple of the row height
+
+// Pause constants
+pub const RESUME_COUNTDOWN: f32 = 3.0; // Seconds before play continues after unpausing
+pub const PAUSE_OPTIONS: [&str; 3] = ["Resume", "Restart", "Quit to Menu"];
+pub const GAME_OVER_OPTIONS: [&str; 2] = ["Retry", "Back to Menu"];
+pub const MATCH_OVER_OPTIONS: [&str; 2] = ["Rematch", "Back to Menu"];
+pub const LEVEL_COMPLETE_OPTIONS: [&str; 3] = ["Next Level", "Retry", "Campaign Menu"];
+
+// Controls screen constants
+pub const CONTROLS_PAGES: [&str; 2] = ["Game and Menus", "Editor"];
+
+// High score constants
+pub const SCORE_TABLE_SIZE: usize = 10;
+pub const LEGACY_SCORE_CATEGORY: &str = "Legacy"; // Migrated from the old highscore.txt
 
 // Audio Constants
-pub const INITIAL_MUSIC_VOLUME: f32 = 0.8;
\ No newline at end of file
+pub const INITIAL_MUSIC_VOLUME: f32 = 0.8;
+pub const PAUSED_MUSIC_FACTOR: f32 = 0.3; // Share of the music volume kept while paused
\ No newline at end of file
@@ -0,0 +1,186 @@
+use crate::constants::*;
+use crate::level::{load_level_files, Goal, Level};
+use nalgebra as na;
+use std::fs;
+use std::path::PathBuf;
+
+// What a left click places on the board
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum EditorTool {
+    Wall,
+    Food,
+    Start,
+}
+
+impl EditorTool {
+    pub fn name(self) -> &'static str {
+        match self {
+            EditorTool::Wall => "Wall",
+            EditorTool::Food => "Food Spot",
+            EditorTool::Start => "Start",
+        }
+    }
+}
+
+// The level being built in the editor. Saved levels are plain level files in
+// `directory`, so they also show up on the level select screen.
+pub struct Editor {
+    pub level: Level,
+    pub tool: EditorTool,
+    pub painting: Option<bool>, // Held mouse button, true paints with the tool and false erases
+    pub name_input: Option<String>, // Characters typed while renaming the level
+    pub status: String,         // Outcome of the last save or load
+    pub directory: PathBuf,
+}
+
+impl Editor {
+    pub fn new(directory: PathBuf) -> Editor {
+        let mut level = Level::open();
+        level.name = EDITOR_DEFAULT_NAME.to_string();
+        Editor {
+            level,
+            tool: EditorTool::Wall,
+            painting: None,
+            name_input: None,
+            status: String::new(),
+            directory,
+        }
+    }
+
+    // Applies the held mouse button to one cell
+    pub fn paint(&mut self, cell: na::Point2<i32>) {
+        let level = &mut self.level;
+        match (self.painting, self.tool) {
+            (Some(true), EditorTool::Wall) if cell != level.start => {
+                level.food_spots.retain(|&spot| spot != cell);
+                if !level.walls.contains(&cell) {
+                    level.walls.push(cell);
+                }
+            }
+            (Some(true), EditorTool::Food) if cell != level.start => {
+                level.walls.retain(|&wall| wall != cell);
+                if !level.food_spots.contains(&cell) {
+                    level.food_spots.push(cell);
+                }
+            }
+            (Some(true), EditorTool::Start) => {
+                level.start = cell;
+                level.walls.retain(|&wall| wall != cell);
+                level.food_spots.retain(|&spot| spot != cell);
+            }
+            (Some(false), _) => {
+                level.walls.retain(|&wall| wall != cell);
+                level.food_spots.retain(|&spot| spot != cell);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.level.walls.clear();
+        self.level.food_spots.clear();
+    }
+
+    // Grows or shrinks the board, dropping whatever falls off the edge
+    pub fn resize(&mut self, width_delta: i32, height_delta: i32) {
+        let level = &mut self.level;
+        level.width = (level.width + width_delta).clamp(MIN_LEVEL_SIZE, MAX_LEVEL_SIZE);
+        level.height = (level.height + height_delta).clamp(MIN_LEVEL_SIZE, MAX_LEVEL_SIZE);
+        let (width, height) = (level.width, level.height);
+        level.start = na::Point2::new(level.start.x.min(width - 1), level.start.y.min(height - 1));
+
+        let start = level.start;
+        let keep = |cell: &na::Point2<i32>| cell.x < width && cell.y < height && *cell != start;
+        level.walls.retain(keep);
+        level.food_spots.retain(keep);
+    }
+
+    // No goal, then each kind of goal with a starting amount
+    pub fn cycle_goal(&mut self) {
+        self.level.goal = match self.level.goal {
+            None => Some(Goal::Length(10)),
+            Some(Goal::Length(_)) => Some(Goal::FoodWithin {
+                food: 10,
+                seconds: 60.0,
+            }),
+            Some(Goal::FoodWithin { .. }) => Some(Goal::Survive(30.0)),
+            Some(Goal::Survive(_)) => None,
+        };
+    }
+
+    // The length or food count of a goal, or the seconds of a survive goal
+    pub fn adjust_goal_amount(&mut self, delta: i32) {
+        self.level.goal = match self.level.goal {
+            Some(Goal::Length(length)) => {
+                Some(Goal::Length((length as i32 + delta).max(2) as usize))
+            }
+            Some(Goal::FoodWithin { food, seconds }) => Some(Goal::FoodWithin {
+                food: (food as i32 + delta).max(1) as u32,
+                seconds,
+            }),
+            Some(Goal::Survive(seconds)) => {
+                Some(Goal::Survive((seconds + 5.0 * delta as f32).max(5.0)))
+            }
+            None => None,
+        };
+    }
+
+    // The clock of a timed goal, in steps of five seconds
+    pub fn adjust_goal_time(&mut self, delta: i32) {
+        self.level.goal = match self.level.goal {
+            Some(Goal::FoodWithin { food, seconds }) => Some(Goal::FoodWithin {
+                food,
+                seconds: (seconds + 5.0 * delta as f32).max(5.0),
+            }),
+            Some(Goal::Survive(seconds)) => {
+                Some(Goal::Survive((seconds + 5.0 * delta as f32).max(5.0)))
+            }
+            goal => goal,
+        };
+    }
+
+    // Saves under a file name made from the level name, true when it was written
+    pub fn save(&mut self) -> bool {
+        let file_name: String = self
+            .level
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path = self.directory.join(format!("{}.txt", file_name));
+        let result = fs::create_dir_all(&self.directory)
+            .and_then(|_| fs::write(&path, self.level.to_text()));
+        match result {
+            Ok(()) => {
+                self.status = format!("Saved {}", path.display());
+                true
+            }
+            Err(e) => {
+                eprintln!("Failed to save level {:?}: {}", path, e);
+                self.status = format!("Failed to save: {}", e);
+                false
+            }
+        }
+    }
+
+    // Opens the saved level after the one being edited, in file name order
+    pub fn load_next(&mut self) {
+        let levels = load_level_files(&self.directory);
+        if levels.is_empty() {
+            self.status = format!("No saved levels in {}", self.directory.display());
+            return;
+        }
+        let next = levels
+            .iter()
+            .position(|level| level.name == self.level.name)
+            .map_or(0, |index| (index + 1) % levels.len());
+        self.level = levels[next].clone();
+        self.status = format!("Opened {}", self.level.name);
+    }
+}
@@ -1,426 +1,844 @@
+use crate::audio::{Music, Sfx};
 use crate::constants::*;
+use crate::editor::EditorTool;
 use crate::game_state::GameState;
+use crate::input::{button_action, dpad_button, Action, InputContext, KeyBindings};
+use crate::menu;
 use crate::types::*;
 use ggez::{
-    audio::SoundSource,
-    event::EventHandler,
-    graphics::{self, Color, DrawMode, DrawParam, Mesh, MeshBuilder, Text},
-    input::keyboard::{KeyCode, KeyInput},
-    mint,
+    event::{Axis, Button, EventHandler},
+    graphics::{self, Color},
+    input::keyboard::{KeyCode, KeyInput, KeyMods},
+    input::gamepad::GamepadId,
+    input::mouse::MouseButton,
     Context,
     GameResult,
 };
-use nalgebra as na;
 
 impl EventHandler for GameState {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
         self.handle_resize(ctx);
+        self.sync_gamepads(ctx);
         match self.mode {
-            GameMode::Menu => {
-                if self.score > self.high_score {
-                    self.high_score = self.score;
-                    self.save_high_score();
-                }
-                // Stop game music if it's playing when returning to menu
-                if self.game_music.playing() {
-                    self.game_music.set_pitch(1.0); // Reset pitch before stopping
-                    self.game_music.stop(ctx)?;
-                }
-                // Stop special music if it's playing
-                if self.special_mode_music.playing() {
-                    self.special_mode_music.stop(ctx)?;
-                }
-                // Start menu music if it's not playing
-                if !self.menu_music.playing() {
-                    self.menu_music.play(ctx)?;
+            GameMode::Menu
+            | GameMode::HighScores
+            | GameMode::LevelSelect
+            | GameMode::Campaign
+            | GameMode::Editor
+            | GameMode::Replays
+            | GameMode::Controls => {
+                // Switch back to menu music, stopping any game music
+                self.audio.play_music(ctx, Music::Menu)?;
+                if self.mode == GameMode::Menu {
+                    self.update_demo(ctx.time.delta().as_secs_f32());
                 }
             }
             GameMode::Playing => {
-                // Stop menu music if it's playing when starting the game
-                if self.menu_music.playing() {
-                    self.menu_music.stop(ctx)?;
+                self.play_game_music(ctx)?;
+
+                // Hold the snake still until the resume countdown runs out
+                if self.resume_countdown > 0.0 {
+                    self.resume_countdown =
+                        (self.resume_countdown - ctx.time.delta().as_secs_f32()).max(0.0);
+                    return Ok(());
                 }
-                // Start appropriate game music if not playing
-                if !self.game_music.playing() && !self.special_mode_music.playing() {
-                    match self.difficulty {
-                        Difficulty::Special => {
-                            // Stop regular game music if playing (safety check)
-                            if self.game_music.playing() {
-                                self.game_music.stop(ctx)?;
-                            }
-                            self.special_mode_music.play(ctx)?;
-                        }
-                        _ => {
-                            // Stop special music if playing (safety check)
-                            if self.special_mode_music.playing() {
-                                self.special_mode_music.stop(ctx)?;
-                            }
-                            self.game_music.set_pitch(self.music_speed);
-                            self.game_music.play(ctx)?;
-                        }
+                self.run_time += ctx.time.delta().as_secs_f32();
+
+                if let Some(ghost) = self.ghost.as_mut() {
+                    ghost.update(ctx.time.delta().as_secs_f32());
+                    if ghost.finished() {
+                        self.ghost = None;
                     }
                 }
 
                 self.last_update += ctx.time.delta().as_secs_f32();
-                if self.last_update >= self.move_time {
+                if self.last_update >= self.sim.move_time {
                     self.last_update = 0.0;
+                    // Advance the rules one tick with any buffered input and the AI's moves
+                    self.advance_tick(ctx)?;
+                }
+            }
+            GameMode::ReplayPlayback => {
+                self.play_game_music(ctx)?;
+                let Some(playback) = &self.playback else {
+                    return Ok(());
+                };
+                if playback.paused || playback.finished {
+                    return Ok(());
+                }
 
-                    // Check for and apply buffered input
-                    if let Some(new_velocity) = self.next_velocity.take() {
-                        self.velocity = new_velocity;
-                    }
-
-                    // Clone the position of the last segment
-                    let last_pos = self.snake_body.last().unwrap().pos;
-                    // Movement for snake
-                    for i in (1..self.snake_body.len()).rev() {
-                        self.snake_body[i].pos = self.snake_body[i - 1].pos;
-                    }
-                    self.snake_body[0].pos += self.velocity;
-
-                    // Check if snake ate the food with adjusted collision detection
-                    let dist_x = (self.snake_body[0].pos.x - self.food.pos.x).abs();
-                    let dist_y = (self.snake_body[0].pos.y - self.food.pos.y).abs();
-                    if dist_x < self.scaled_snake_size - EPSILON
-                        && dist_y < self.scaled_snake_size - EPSILON
-                    {
-                        // Play eat sound
-                        self.eat_sound.play(ctx)?;
-
-                        // Eat the food and grow
-                        self.snake_body.push(SnakeSegment { pos: last_pos });
-                        self.score += 1;
-
-                        // If in Special difficulty, increase speed
-                        if let Difficulty::Special = self.difficulty {
-                            // Reduce the move time by the speed-up factor
-                            self.move_time *= SPEED_UP_FACTOR;
-                            // Ensure move_time doesn't go below a certain minimum
-                            if self.move_time < MIN_MOVE_TIME {
-                                self.move_time = MIN_MOVE_TIME;
-                            }
-                        }
-
-                        // Generate new food position and ensure it doesn't overlap with the snake
-                        loop {
-                            self.food.pos = GameState::get_random_food_position(
-                                self.boundary_width,
-                                self.boundary_height,
-                                self.scale,
-                            );
-                            if !self
-                                .snake_body
-                                .iter()
-                                .any(|segment| segment.pos == self.food.pos)
-                            {
-                                break;
-                            }
-                        }
-                    }
-
-                    // Check for collisions with self
-                    for segment in &self.snake_body[1..] {
-                        if self.snake_body[0].pos == segment.pos {
-                            self.mode = GameMode::Menu;
-                            // Play game over sound
-                            self.game_over_sound.play(ctx)?;
-                            // Stop game music on game over
-                            self.game_music.set_pitch(1.0); // Reset pitch
-                            self.game_music.stop(ctx)?;
-                            // Stop special music if playing
-                            if self.special_mode_music.playing() {
-                                self.special_mode_music.stop(ctx)?;
-                            }
-                            break;
-                        }
-                    }
-
-                    // Check for boundary collisions
-                    if self.check_border_collisions() {
-                        self.mode = GameMode::Menu;
-                        // Play game over sound
-                        self.game_over_sound.play(ctx)?;
-                        // Stop game music on game over
-                        self.game_music.set_pitch(1.0); // Reset pitch
-                        self.game_music.stop(ctx)?;
-                        // Stop special music if playing
-                        if self.special_mode_music.playing() {
-                            self.special_mode_music.stop(ctx)?;
-                        }
-                    }
+                // Several ticks can fall in one frame at the faster speeds
+                self.last_update += ctx.time.delta().as_secs_f32() * REPLAY_SPEEDS[playback.speed];
+                while self.last_update >= self.sim.move_time
+                    && self.playback.as_ref().is_some_and(|playback| !playback.finished)
+                {
+                    self.last_update -= self.sim.move_time;
+                    self.advance_tick(ctx)?;
                 }
             }
+            GameMode::Paused => {
+                // Nothing advances while paused, last_update stays where it was
+            }
+            GameMode::GameOver
+            | GameMode::LevelComplete
+            | GameMode::RoundOver
+            | GameMode::MatchOver => {
+                // The run summary is frozen until the player picks an option
+            }
         }
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        let mut canvas = graphics::Canvas::from_frame(ctx, Some(Color::BLACK));
-        let mut mesh_builder = MeshBuilder::new();
+        let mut canvas =
+            graphics::Canvas::from_frame(ctx, Some(self.theme.palette().background));
 
         match self.mode {
             GameMode::Menu => {
-                let mut menu_text = Text::new(format!("Press Enter to Start\n     ESC to Exit\n"));
-                menu_text.set_scale(graphics::PxScale::from(60.0 * self.scale));
-                canvas.draw(
-                    &menu_text,
-                    DrawParam::default().dest(mint::Point2 {
-                        x: self.boundary_width * 0.5 - (350.0 * self.scale) + self.offset_x,
-                        y: self.boundary_height * 0.35 + self.offset_y,
-                    }),
-                );
+                // The attract mode demo plays under the menu
+                if self.demo {
+                    self.draw_board(ctx, &mut canvas)?;
+                    self.draw_dim_overlay(ctx, &mut canvas)?;
+                    let yellow = Color::from_rgb(255, 255, 0);
+                    self.draw_centered_text(
+                        ctx,
+                        &mut canvas,
+                        "Demo - press any key",
+                        30.0,
+                        0.01,
+                        yellow,
+                    )?;
+                }
+                self.draw_menu(ctx, &mut canvas)?;
+            }
+            GameMode::HighScores => {
+                self.draw_high_scores(ctx, &mut canvas)?;
+            }
+            GameMode::LevelSelect => {
+                self.draw_level_select(ctx, &mut canvas)?;
+            }
+            GameMode::Campaign => {
+                self.draw_campaign(ctx, &mut canvas)?;
+            }
+            GameMode::Editor => {
+                self.draw_editor(ctx, &mut canvas)?;
+            }
+            GameMode::Playing => {
+                self.draw_playfield(ctx, &mut canvas)?;
 
-                // Draw difficulty text separately for each difficulty level with different colors
-                let easy_color = if let Difficulty::Easy = self.difficulty {
-                    Color::from_rgb(0, 255, 0) // Green for selected
-                } else {
-                    Color::from_rgb(180, 255, 200) // Light green for unselected
-                };
-                let normal_color = if let Difficulty::Normal = self.difficulty {
-                    Color::from_rgb(255, 255, 0) // Yellow for selected
-                } else {
-                    Color::from_rgb(255, 255, 200) // Light yellow for unselected
-                };
-                let hard_color = if let Difficulty::Hard = self.difficulty {
-                    Color::from_rgb(255, 0, 0) // Red for selected
-                } else {
-                    Color::from_rgb(255, 200, 200) // Light red for unselected
-                };
-                let special_color = if let Difficulty::Special = self.difficulty {
-                    Color::from_rgb(255, 100, 255) // Purple for selected
-                } else {
-                    Color::from_rgb(255, 200, 255) // Light purple for unselected
-                };
+                if self.resume_countdown > 0.0 {
+                    let countdown = format!("{}", self.resume_countdown.ceil() as i32);
+                    let yellow = Color::from_rgb(255, 255, 0);
+                    self.draw_centered_text(ctx, &mut canvas, &countdown, 150.0, 0.4, yellow)?;
+                }
+            }
+            GameMode::Paused => {
+                self.draw_playfield(ctx, &mut canvas)?;
+                self.draw_pause_overlay(ctx, &mut canvas)?;
+            }
+            GameMode::GameOver => {
+                self.draw_playfield(ctx, &mut canvas)?;
+                self.draw_game_over(ctx, &mut canvas)?;
+            }
+            GameMode::LevelComplete => {
+                self.draw_playfield(ctx, &mut canvas)?;
+                self.draw_level_complete(ctx, &mut canvas)?;
+            }
+            GameMode::RoundOver => {
+                self.draw_playfield(ctx, &mut canvas)?;
+                self.draw_round_over(ctx, &mut canvas)?;
+            }
+            GameMode::MatchOver => {
+                self.draw_playfield(ctx, &mut canvas)?;
+                self.draw_match_over(ctx, &mut canvas)?;
+            }
+            GameMode::Replays => {
+                self.draw_replays(ctx, &mut canvas)?;
+            }
+            GameMode::Controls => {
+                self.draw_controls(ctx, &mut canvas)?;
+            }
+            GameMode::ReplayPlayback => {
+                self.draw_playfield(ctx, &mut canvas)?;
+                self.draw_playback_overlay(ctx, &mut canvas)?;
+            }
+        }
 
-                let mut easy_text = Text::new("1: Easy");
-                easy_text.set_scale(graphics::PxScale::from(50.0 * self.scale));
-                canvas.draw(
-                    &easy_text,
-                    DrawParam::default()
-                        .dest(mint::Point2 {
-                            x: self.boundary_width * 0.5 - (350.0 * self.scale) + self.offset_x,
-                            y: self.boundary_height * 0.50 + self.offset_y,
-                        })
-                        .color(easy_color),
-                );
+        if self.show_fps {
+            self.draw_fps(ctx, &mut canvas);
+        }
+        canvas.finish(ctx)?;
+        Ok(())
+    }
 
-                let mut normal_text = Text::new("2: Normal");
-                normal_text.set_scale(graphics::PxScale::from(50.0 * self.scale));
-                canvas.draw(
-                    &normal_text,
-                    DrawParam::default()
-                        .dest(mint::Point2 {
-                            x: self.boundary_width * 0.67 - (350.0 * self.scale) + self.offset_x,
-                            y: self.boundary_height * 0.50 + self.offset_y,
-                        })
-                        .color(normal_color),
-                );
+    fn key_down_event(&mut self, ctx: &mut Context, key: KeyInput, repeat: bool) -> GameResult {
+        // Fullscreen works on every screen, Alt+Enter must not also confirm
+        let enter = matches!(key.keycode, Some(KeyCode::Return | KeyCode::NumpadEnter));
+        if key.keycode == Some(KeyCode::F11) || (enter && key.mods.contains(KeyMods::ALT)) {
+            if !repeat {
+                self.toggle_fullscreen(ctx);
+            }
+            return Ok(());
+        }
 
-                let mut hard_text = Text::new("3: Hard");
-                hard_text.set_scale(graphics::PxScale::from(50.0 * self.scale));
-                canvas.draw(
-                    &hard_text,
-                    DrawParam::default()
-                        .dest(mint::Point2 {
-                            x: self.boundary_width * 0.88 - (350.0 * self.scale) + self.offset_x,
-                            y: self.boundary_height * 0.50 + self.offset_y,
-                        })
-                        .color(hard_color),
-                );
+        // The action the key is bound to on this screen, text entry still reads the key itself
+        let action = key
+            .keycode
+            .and_then(|keycode| self.key_bindings.action(keycode, self.input_context()));
+        self.handle_input(ctx, action, key.keycode, repeat)
+    }
 
-                let mut special_text = Text::new("4: Special");
-                special_text.set_scale(graphics::PxScale::from(50.0 * self.scale));
-                canvas.draw(
-                    &special_text,
-                    DrawParam::default()
-                        .dest(mint::Point2 {
-                            x: self.boundary_width * 0.65 - (350.0 * self.scale) + self.offset_x,
-                            y: self.boundary_height * 0.57 + self.offset_y,
-                        })
-                        .color(special_color),
-                );
+    fn gamepad_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: Button,
+        id: GamepadId,
+    ) -> GameResult {
+        self.handle_gamepad_button(ctx, button, id)
+    }
 
-                let mut volume_text = Text::new(format!(
-                    "Volume: {:.0}% (+/- to change)",
-                    self.music_volume * 100.0
-                ));
-                volume_text.set_scale(graphics::PxScale::from(40.0 * self.scale));
-                canvas.draw(
-                    &volume_text,
-                    DrawParam::default()
-                        .dest(mint::Point2 {
-                            x: self.boundary_width * 0.5 - (300.0 * self.scale) + self.offset_x,
-                            y: self.boundary_height * 0.75 + self.offset_y,
-                        })
-                        .color(Color::WHITE),
-                );
+    fn gamepad_axis_event(
+        &mut self,
+        ctx: &mut Context,
+        axis: Axis,
+        value: f32,
+        id: GamepadId,
+    ) -> GameResult {
+        let axis = match axis {
+            Axis::LeftStickX => 0,
+            Axis::LeftStickY => 1,
+            _ => return Ok(()),
+        };
+        self.sync_gamepads(ctx);
+        let Some(slot) = self.gamepads.iter_mut().find(|slot| slot.id == id) else {
+            return Ok(());
+        };
+        // Pushing the stick is pressing the d-pad the same way
+        match slot.move_stick(axis, value) {
+            Some(direction) => self.handle_gamepad_button(ctx, dpad_button(direction), id),
+            None => Ok(()),
+        }
+    }
 
-                let mut menu_high_score_text =
-                    Text::new(format!("High Score: {}", self.high_score));
-                menu_high_score_text.set_scale(graphics::PxScale::from(60.0 * self.scale));
-                canvas.draw(
-                    &menu_high_score_text,
-                    DrawParam::default()
-                        .dest(mint::Point2 {
-                            x: self.boundary_width * 0.61 - (350.0 * self.scale) + self.offset_x,
-                            y: self.boundary_height * 0.65 + self.offset_y,
-                        })
-                        .color(Color::from_rgb(0, 255, 0)),
-                );
-            }
-            GameMode::Playing => {
-                // Draw the snake
-                for segment in &self.snake_body {
-                    mesh_builder.rectangle(
-                        DrawMode::fill(),
-                        self.scaled_rect(segment.pos),
-                        Color::from_rgb(50, 150, 50),
-                    )?;
-                }
+    fn focus_event(&mut self, _ctx: &mut Context, gained: bool) -> GameResult {
+        // Don't let the snake run into a wall while the window is in the background
+        if !gained && self.mode == GameMode::Playing {
+            self.pause();
+        }
+        Ok(())
+    }
 
-                // Draw the food
-                mesh_builder.rectangle(
-                    DrawMode::fill(),
-                    self.scaled_rect(self.food.pos),
-                    Color::from_rgb(255, 0, 0),
-                )?;
+    fn quit_event(&mut self, ctx: &mut Context) -> GameResult<bool> {
+        self.capture_window_settings(ctx);
+        self.save_settings();
+        Ok(false)
+    }
 
-                // Draw the game boundary (red border)
-                self.draw_border(&mut mesh_builder);
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        self.idle_time = 0.0;
+        if self.demo {
+            self.stop_demo();
+            return Ok(());
+        }
+        if self.mode == GameMode::Menu && self.seed_input.is_some() {
+            // A click ends the seed entry, the right button drops what was typed
+            self.finish_seed_input(button != MouseButton::Right);
+            return self.audio.play_sfx(ctx, Sfx::MenuChange);
+        }
+        if self.mode == GameMode::Menu {
+            // A click picks the row under the mouse, the right button steps options back
+            let Some(row) = menu::hit(&self.menu_layout(ctx)?, x, y) else {
+                return Ok(());
+            };
+            self.menu.focus = row;
+            let changed = match button {
+                MouseButton::Left => {
+                    self.activate_menu_item(ctx);
+                    true
+                }
+                MouseButton::Right => self.adjust_menu_item(ctx, -1),
+                _ => false,
+            };
+            if changed {
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+        }
+        if self.mode == GameMode::Editor && self.editor.name_input.is_none() {
+            self.editor.painting = match button {
+                MouseButton::Left => Some(true),
+                MouseButton::Right => Some(false),
+                _ => None,
+            };
+            if let Some(cell) = self.cell_at(x, y) {
+                self.editor.paint(cell);
+            }
+        }
+        Ok(())
+    }
 
-                let mesh = Mesh::from_data(ctx, mesh_builder.build());
-                canvas.draw(&mesh, DrawParam::default());
+    fn mouse_button_up_event(
+        &mut self,
+        _ctx: &mut Context,
+        _button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) -> GameResult {
+        self.editor.painting = None;
+        Ok(())
+    }
 
-                // Draw score
-                let mut score_text = Text::new(format!("Score: {}", self.score));
-                score_text.set_scale(graphics::PxScale::from(40.0 * self.scale));
-                canvas.draw(
-                    &score_text,
-                    DrawParam::default().dest(mint::Point2 {
-                        x: (10.0 * self.scale) + self.offset_x,
-                        y: (60.0 * self.scale) + self.offset_y,
-                    }),
-                );
-                let mut high_score_text = Text::new(format!("High Score: {}", self.high_score));
-                high_score_text.set_scale(graphics::PxScale::from(40.0 * self.scale));
-                canvas.draw(
-                    &high_score_text,
-                    DrawParam::default()
-                        .dest(mint::Point2 {
-                            x: (10.0 * self.scale) + self.offset_x,
-                            y: (10.0 * self.scale) + self.offset_y,
-                        })
-                        .color(Color::from_rgb(0, 255, 0)),
-                );
+    fn mouse_motion_event(
+        &mut self,
+        ctx: &mut Context,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    ) -> GameResult {
+        if !self.demo {
+            self.idle_time = 0.0; // Moving the mouse keeps the demo away, it doesn't end it
+        }
+        if self.mode == GameMode::Menu && self.seed_input.is_none() {
+            // Hovering a row focuses it, so keys and mouse can be mixed
+            if let Some(row) = menu::hit(&self.menu_layout(ctx)?, x, y) {
+                self.menu.focus = row;
+            }
+        }
+        if self.mode == GameMode::Editor && self.editor.painting.is_some() {
+            // Fill in the cells between motion events so fast strokes stay unbroken
+            let half_cell = self.scaled_snake_size * 0.5;
+            let steps = ((dx.abs().max(dy.abs()) / half_cell).ceil() as i32).max(1);
+            for step in 1..=steps {
+                let remaining = 1.0 - step as f32 / steps as f32;
+                if let Some(cell) = self.cell_at(x - dx * remaining, y - dy * remaining) {
+                    self.editor.paint(cell);
+                }
             }
         }
+        Ok(())
+    }
 
-        canvas.finish(ctx)?;
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) -> GameResult {
+        if let Some(input) = self.editor.name_input.as_mut() {
+            // Colons would break the level header
+            let allowed = character.is_ascii_alphanumeric() || " -'".contains(character);
+            if allowed && input.chars().count() < EDITOR_NAME_LENGTH {
+                input.push(character);
+            }
+            return Ok(());
+        }
+        if let Some(input) = self.seed_input.as_mut() {
+            // Only keep digits that still fit in a u64
+            if character.is_ascii_digit() {
+                input.push(character);
+                if input.parse::<u64>().is_err() {
+                    input.pop();
+                }
+            }
+        }
         Ok(())
     }
+}
+
+impl GameState {
+    // A button on a gamepad, taken through the same handling as keys with the meaning it
+    // has for the pad's player
+    fn handle_gamepad_button(
+        &mut self,
+        ctx: &mut Context,
+        button: Button,
+        id: GamepadId,
+    ) -> GameResult {
+        // Events can arrive before the update that would have noticed a new pad
+        self.sync_gamepads(ctx);
+        let context = self.input_context();
+        let paused = self.mode == GameMode::Paused;
+        let Some(slot) = self.gamepads.iter_mut().find(|slot| slot.id == id) else {
+            return Ok(());
+        };
+        if button == Button::Select && context == InputContext::Menu {
+            // Hands the pad to the next player
+            slot.player = (slot.player + 1) % MAX_PLAYERS;
+            return self.audio.play_sfx(ctx, Sfx::MenuChange);
+        }
+        let action = if paused && button == Button::Start {
+            // Start paused the run, so it resumes it instead of picking the highlighted option
+            Some(Action::Pause)
+        } else {
+            button_action(button, context, slot.player)
+        };
+        self.handle_input(ctx, action, None, false)
+    }
+
+    // Key presses and gamepad buttons alike. `action` is what the input means on this
+    // screen, `keycode` is only there for keyboard input, for text entry and the few keys
+    // that can't be rebound.
+    fn handle_input(
+        &mut self,
+        ctx: &mut Context,
+        action: Option<Action>,
+        keycode: Option<KeyCode>,
+        repeat: bool,
+    ) -> GameResult {
+        // Any input wakes the menu from the demo and does nothing else
+        self.idle_time = 0.0;
+        if self.demo {
+            self.stop_demo();
+            return Ok(());
+        }
+
+        // Mute works everywhere except while typing a seed or level name or binding a key
+        if action == Some(Action::Mute)
+            && self.seed_input.is_none()
+            && self.editor.name_input.is_none()
+            && self.rebinding.is_none()
+        {
+            self.audio.toggle_mute();
+            self.save_settings();
+            return Ok(());
+        }
 
-    fn key_down_event(&mut self, ctx: &mut Context, key: KeyInput, _repeat: bool) -> GameResult {
         match self.mode {
+            GameMode::Menu if self.seed_input.is_some() => {
+                // Entering a seed, the digits themselves arrive through text_input_event.
+                // Confirm and Back also come from a pad, so it can leave the entry too.
+                match (keycode, action) {
+                    (Some(KeyCode::Back), _) => {
+                        if let Some(input) = self.seed_input.as_mut() {
+                            input.pop();
+                        }
+                    }
+                    (Some(KeyCode::NumpadEnter), _) | (_, Some(Action::Confirm)) => {
+                        self.finish_seed_input(true);
+                    }
+                    (_, Some(Action::Back)) => self.finish_seed_input(false),
+                    _ => return Ok(()), // Ignore other keys
+                }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
             GameMode::Menu => {
                 // Play sound on any relevant key press in the menu
-                let mut play_sound = false;
-                let mut volume_changed = false;
+                let mut play_sound = true;
+                let mut settings_changed = false;
+
+                match action {
+                    Some(Action::MenuUp) => {
+                        self.menu.move_focus(-1, self.menu_items().len());
+                    }
+                    Some(Action::MenuDown) => {
+                        self.menu.move_focus(1, self.menu_items().len());
+                    }
+                    Some(Action::MenuLeft) => play_sound = self.adjust_menu_item(ctx, -1),
+                    Some(Action::MenuRight) => play_sound = self.adjust_menu_item(ctx, 1),
+                    Some(Action::Confirm) => self.activate_menu_item(ctx),
+                    Some(Action::Back) => {
+                        if !self.menu_back() {
+                            ctx.request_quit();
+                        }
+                    }
+                    Some(Action::Easy) => {
+                        self.difficulty = Difficulty::Easy;
+                        settings_changed = true;
+                    }
+                    Some(Action::Normal) => {
+                        self.difficulty = Difficulty::Normal;
+                        settings_changed = true;
+                    }
+                    Some(Action::Hard) => {
+                        self.difficulty = Difficulty::Hard;
+                        settings_changed = true;
+                    }
+                    Some(Action::Special) => {
+                        self.difficulty = Difficulty::Special;
+                        settings_changed = true;
+                    }
+                    Some(Action::VolumeUp) => {
+                        self.adjust_master_volume(0.1);
+                        settings_changed = true;
+                    }
+                    Some(Action::VolumeDown) => {
+                        self.adjust_master_volume(-0.1);
+                        settings_changed = true;
+                    }
+                    _ => return Ok(()), // Ignore other keys
+                }
+                if settings_changed {
+                    self.save_settings();
+                }
+                if play_sound {
+                    self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+                }
+            }
+            GameMode::Playing => {
+                // With two players WASD is player one and the arrows player two, alone both steer
+                let arrows = match self.play_mode {
+                    PlayMode::Solo | PlayMode::Rivals => 0,
+                    PlayMode::Versus | PlayMode::Coop => 1,
+                };
+                let (player, new_direction) = match (action, keycode) {
+                    (Some(Action::MoveRight), _) => (0, Direction::Right),
+                    (Some(Action::MoveLeft), _) => (0, Direction::Left),
+                    (Some(Action::MoveUp), _) => (0, Direction::Up),
+                    (Some(Action::MoveDown), _) => (0, Direction::Down),
+                    (Some(Action::Player2Right), _) => (arrows, Direction::Right),
+                    (Some(Action::Player2Left), _) => (arrows, Direction::Left),
+                    (Some(Action::Player2Up), _) => (arrows, Direction::Up),
+                    (Some(Action::Player2Down), _) => (arrows, Direction::Down),
+                    (Some(Action::Pause), _) => {
+                        self.pause();
+                        return Ok(()); // Return early as we're switching mode
+                    }
+                    (_, Some(KeyCode::F8)) => {
+                        // Not on any screen, lets the autopilot play long runs for testing
+                        self.autopilot = !self.autopilot;
+                        self.assisted |= self.autopilot;
+                        return Ok(());
+                    }
+                    _ => return Ok(()), // Ignore other keys
+                };
 
-                if let Some(keycode) = key.keycode {
-                    match keycode {
-                        KeyCode::Return => {
-                            // Start the game
-                            self.mode = GameMode::Playing;
+                self.queue_turn(player, new_direction);
+            }
+            GameMode::Paused => {
+                match action {
+                    Some(Action::MenuUp) => {
+                        self.pause_selection =
+                            (self.pause_selection + PAUSE_OPTIONS.len() - 1) % PAUSE_OPTIONS.len();
+                    }
+                    Some(Action::MenuDown) => {
+                        self.pause_selection = (self.pause_selection + 1) % PAUSE_OPTIONS.len();
+                    }
+                    Some(Action::Back | Action::Pause) => self.resume(),
+                    // The key that paused the game unpauses it too
+                    _ if self.key_bindings.is_bound(Action::Pause, keycode) => self.resume(),
+                    Some(Action::Confirm) => match self.pause_selection {
+                        0 => self.resume(),
+                        1 => {
+                            self.resume();
                             self.reset_game_state();
-                            play_sound = true;
                         }
-                        KeyCode::Escape => {
-                            ctx.request_quit();
-                            play_sound = true;
+                        _ => self.quit_to_menu(),
+                    },
+                    _ => return Ok(()), // Ignore other keys
+                }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+            GameMode::HighScores => {
+                let count = self.score_categories().len();
+                match (action, keycode) {
+                    (Some(Action::MenuLeft), _) => {
+                        self.score_table_index = (self.score_table_index + count - 1) % count;
+                    }
+                    (Some(Action::MenuRight), _) => {
+                        self.score_table_index = (self.score_table_index + 1) % count;
+                    }
+                    (Some(Action::Back | Action::Confirm), _) => {
+                        self.mode = GameMode::Menu;
+                    }
+                    _ => return Ok(()), // Ignore other keys
+                }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+            GameMode::LevelSelect => {
+                let count = self.levels.len();
+                match (action, keycode) {
+                    (Some(Action::MenuUp), _) => {
+                        self.level_index = (self.level_index + count - 1) % count;
+                    }
+                    (Some(Action::MenuDown), _) => {
+                        self.level_index = (self.level_index + 1) % count;
+                    }
+                    (Some(Action::Confirm), _) => {
+                        self.play_mode = PlayMode::Solo;
+                        self.campaign_index = None;
+                        self.mode = GameMode::Playing;
+                        self.reset_game_state();
+                    }
+                    (Some(Action::Back), _) => {
+                        self.mode = GameMode::Menu;
+                    }
+                    _ => return Ok(()), // Ignore other keys
+                }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+            GameMode::Campaign => {
+                let count = self.campaign.len().max(1);
+                match (action, keycode) {
+                    (Some(Action::MenuUp), _) => {
+                        self.campaign_selection = (self.campaign_selection + count - 1) % count;
+                    }
+                    (Some(Action::MenuDown), _) => {
+                        self.campaign_selection = (self.campaign_selection + 1) % count;
+                    }
+                    (Some(Action::Confirm), _)
+                        if !self.campaign.is_empty()
+                            && self
+                                .campaign_progress
+                                .is_unlocked(&self.campaign, self.campaign_selection) =>
+                    {
+                        self.play_mode = PlayMode::Solo;
+                        self.campaign_index = Some(self.campaign_selection);
+                        self.mode = GameMode::Playing;
+                        self.reset_game_state();
+                    }
+                    (Some(Action::Back), _) => {
+                        self.mode = GameMode::Menu;
+                    }
+                    _ => return Ok(()), // Ignore other keys, including locked levels
+                }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+            GameMode::Editor if self.editor.name_input.is_some() => {
+                // Renaming, the characters themselves arrive through text_input_event
+                match (keycode, action) {
+                    (Some(KeyCode::Back), _) => {
+                        if let Some(input) = self.editor.name_input.as_mut() {
+                            input.pop();
                         }
-                        KeyCode::Key1 => {
-                            // Set difficulty to Easy
-                            if !matches!(self.difficulty, Difficulty::Easy) {
-                                self.difficulty = Difficulty::Easy;
-                                play_sound = true;
-                            }
+                    }
+                    (Some(KeyCode::NumpadEnter), _) | (_, Some(Action::Confirm)) => {
+                        let input = self.editor.name_input.take().unwrap_or_default();
+                        if !input.trim().is_empty() {
+                            self.editor.level.name = input.trim().to_string();
                         }
-                        KeyCode::Key2 => {
-                            // Set difficulty to Normal
-                            if !matches!(self.difficulty, Difficulty::Normal) {
-                                self.difficulty = Difficulty::Normal;
-                                play_sound = true;
+                    }
+                    (_, Some(Action::Back)) => {
+                        self.editor.name_input = None;
+                    }
+                    _ => return Ok(()), // Ignore other keys
+                }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+            GameMode::Editor => {
+                match action {
+                    Some(Action::WallTool) => self.editor.tool = EditorTool::Wall,
+                    Some(Action::FoodTool) => self.editor.tool = EditorTool::Food,
+                    Some(Action::StartTool) => self.editor.tool = EditorTool::Start,
+                    Some(Action::FaceUp) => self.editor.level.direction = Direction::Up,
+                    Some(Action::FaceDown) => self.editor.level.direction = Direction::Down,
+                    Some(Action::FaceLeft) => self.editor.level.direction = Direction::Left,
+                    Some(Action::FaceRight) => self.editor.level.direction = Direction::Right,
+                    Some(Action::Narrower) => self.editor.resize(-1, 0),
+                    Some(Action::Wider) => self.editor.resize(1, 0),
+                    Some(Action::Shorter) => self.editor.resize(0, -1),
+                    Some(Action::Taller) => self.editor.resize(0, 1),
+                    Some(Action::NextGoal) => self.editor.cycle_goal(),
+                    Some(Action::LessGoal) => self.editor.adjust_goal_amount(-1),
+                    Some(Action::MoreGoal) => self.editor.adjust_goal_amount(1),
+                    Some(Action::LessGoalTime) => self.editor.adjust_goal_time(-1),
+                    Some(Action::MoreGoalTime) => self.editor.adjust_goal_time(1),
+                    Some(Action::ClearLevel) => self.editor.clear(),
+                    Some(Action::RenameLevel) => {
+                        self.editor.name_input = Some(self.editor.level.name.clone());
+                    }
+                    Some(Action::SaveLevel) => self.save_editor_level(),
+                    Some(Action::OpenLevel) => self.editor.load_next(),
+                    Some(Action::Confirm) => {
+                        // Play the level exactly as it stands, Back returns here
+                        self.playtesting = true;
+                        self.play_mode = PlayMode::Solo;
+                        self.campaign_index = None;
+                        self.editor.painting = None;
+                        self.mode = GameMode::Playing;
+                        self.reset_game_state();
+                    }
+                    Some(Action::Back) => {
+                        self.playtesting = false;
+                        self.editor.painting = None;
+                        self.mode = GameMode::Menu;
+                    }
+                    _ => return Ok(()), // Ignore other keys
+                }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+            GameMode::LevelComplete => {
+                let count = LEVEL_COMPLETE_OPTIONS.len();
+                match action {
+                    Some(Action::MenuUp) => {
+                        self.level_complete_selection =
+                            (self.level_complete_selection + count - 1) % count;
+                    }
+                    Some(Action::MenuDown) => {
+                        self.level_complete_selection = (self.level_complete_selection + 1) % count;
+                    }
+                    Some(Action::Confirm) => {
+                        let index = self.campaign_index.unwrap_or(0);
+                        match self.level_complete_selection {
+                            0 if index + 1 < self.campaign.len() => {
+                                // On to the level this one just unlocked
+                                self.campaign_index = Some(index + 1);
+                                self.campaign_selection = index + 1;
+                                self.mode = GameMode::Playing;
+                                self.reset_game_state();
                             }
-                        }
-                        KeyCode::Key3 => {
-                            // Set difficulty to Hard
-                            if !matches!(self.difficulty, Difficulty::Hard) {
-                                self.difficulty = Difficulty::Hard;
-                                play_sound = true;
+                            1 => {
+                                self.mode = GameMode::Playing;
+                                self.reset_game_state();
                             }
-                        }
-                        KeyCode::Key4 => {
-                            // Set difficulty to Special
-                            if !matches!(self.difficulty, Difficulty::Special) {
-                                self.difficulty = Difficulty::Special;
-                                play_sound = true;
+                            _ => {
+                                self.campaign_selection = index;
+                                self.mode = GameMode::Campaign;
                             }
                         }
-                        KeyCode::Equals | KeyCode::Plus => {
-                            // Increase volume
-                            self.music_volume = (self.music_volume + 0.1).min(1.0);
-                            volume_changed = true;
-                        }
-                        KeyCode::Minus => {
-                            // Decrease volume
-                            self.music_volume = (self.music_volume - 0.1).max(0.0);
-                            volume_changed = true;
-                        }
-                        _ => {} // Ignore other keys
                     }
+                    Some(Action::Back) => {
+                        self.mode = GameMode::Campaign;
+                    }
+                    _ => return Ok(()), // Ignore other keys
                 }
-                if play_sound {
-                    // Stop the sound first to allow retriggering if pressed quickly
-                    self.menu_change_sound.stop(ctx)?;
-                    self.menu_change_sound.play(ctx)?;
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+            GameMode::RoundOver => {
+                match action {
+                    Some(Action::Confirm) => {
+                        self.round += 1;
+                        self.mode = GameMode::Playing;
+                        self.reset_game_state();
+                    }
+                    Some(Action::Back) => {
+                        self.mode = GameMode::Menu;
+                    }
+                    _ => return Ok(()), // Ignore other keys
                 }
-                if volume_changed {
-                    self.menu_music.set_volume(self.music_volume);
-                    self.game_music.set_volume(self.music_volume);
-                    self.eat_sound.set_volume(self.music_volume);
-                    self.game_over_sound.set_volume(self.music_volume);
-                    self.special_mode_music.set_volume(self.music_volume);
-                    self.menu_change_sound.stop(ctx)?;
-                    self.menu_change_sound.play(ctx)?;
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+            GameMode::MatchOver => {
+                match action {
+                    Some(Action::MenuUp | Action::MenuDown) => {
+                        self.game_over_selection = 1 - self.game_over_selection;
+                    }
+                    Some(Action::Confirm) if self.game_over_selection == 0 => {
+                        self.start_versus();
+                    }
+                    Some(Action::Confirm | Action::Back) => {
+                        self.mode = GameMode::Menu;
+                    }
+                    _ => return Ok(()), // Ignore other keys
                 }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
             }
-            GameMode::Playing => {
-                let new_velocity = match key.keycode {
-                    Some(KeyCode::Right) | Some(KeyCode::D) => na::Vector2::new(self.scaled_snake_size, 0.0),
-                    Some(KeyCode::Left) | Some(KeyCode::A) => na::Vector2::new(-self.scaled_snake_size, 0.0),
-                    Some(KeyCode::Up) | Some(KeyCode::W) => na::Vector2::new(0.0, -self.scaled_snake_size),
-                    Some(KeyCode::Down) | Some(KeyCode::S) => na::Vector2::new(0.0, self.scaled_snake_size),
-                    Some(KeyCode::Escape) => {
+            GameMode::Controls if self.rebinding.is_some() => {
+                // Waiting for the new key, Escape keeps the old one. Repeats of the Enter
+                // that started the wait are not a new key.
+                match (self.rebinding, keycode) {
+                    _ if repeat => return Ok(()),
+                    (_, Some(KeyCode::Escape)) => self.rebinding = None,
+                    (_, None) if action == Some(Action::Back) => self.rebinding = None,
+                    (Some(rebinding), Some(keycode)) => self.rebind(rebinding, keycode),
+                    _ => return Ok(()), // Keys without a code can't be bound
+                }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+            GameMode::Controls => {
+                // The arrows, Enter and Escape always work here so bad bindings can be undone
+                let actions = self.controls_actions();
+                let count = actions.len() + 1;
+                match (action, keycode) {
+                    (Some(Action::MenuUp), _) | (_, Some(KeyCode::Up)) => {
+                        self.controls_selection = (self.controls_selection + count - 1) % count;
+                    }
+                    (Some(Action::MenuDown), _) | (_, Some(KeyCode::Down)) => {
+                        self.controls_selection = (self.controls_selection + 1) % count;
+                    }
+                    (Some(Action::MenuLeft | Action::MenuRight), _)
+                    | (_, Some(KeyCode::Left | KeyCode::Right)) => {
+                        self.controls_page = (self.controls_page + 1) % CONTROLS_PAGES.len();
+                        self.controls_selection = 0;
+                    }
+                    (Some(Action::Back), _) | (_, Some(KeyCode::Escape)) => {
+                        self.rebinding = None;
                         self.mode = GameMode::Menu;
-                        // Reset pitch when escaping to menu
-                        self.game_music.set_pitch(1.0);
-                        // Stop special music if playing
-                        if self.special_mode_music.playing() {
-                            self.special_mode_music.stop(ctx)?;
+                    }
+                    (Some(Action::Confirm), _) | (_, Some(KeyCode::Return)) => {
+                        match actions.get(self.controls_selection) {
+                            Some(&selected) => {
+                                self.rebinding = Some(selected);
+                                self.controls_message = None;
+                            }
+                            None => {
+                                self.key_bindings = KeyBindings::default();
+                                self.controls_message =
+                                    Some("Controls reset to defaults".to_string());
+                                self.save_settings();
+                            }
                         }
-                        return Ok(()); // Return early as we're switching mode
                     }
                     _ => return Ok(()), // Ignore other keys
+                }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+            GameMode::Replays => {
+                let count = self.replays.len().max(1);
+                match (action, keycode) {
+                    (Some(Action::MenuUp), _) => {
+                        self.replay_selection = (self.replay_selection + count - 1) % count;
+                    }
+                    (Some(Action::MenuDown), _) => {
+                        self.replay_selection = (self.replay_selection + 1) % count;
+                    }
+                    (Some(Action::Confirm), _) => self.start_playback(),
+                    (Some(Action::DeleteReplay), _) => self.delete_replay(),
+                    (Some(Action::Back), _) => {
+                        self.mode = GameMode::Menu;
+                    }
+                    _ => return Ok(()), // Ignore other keys
+                }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+            GameMode::ReplayPlayback => {
+                let Some(playback) = self.playback.as_mut() else {
+                    return Ok(());
                 };
-
-                // Prevent immediate reversal
-                // Check against current velocity OR buffered velocity if it exists
-                let current_check_velocity = self.next_velocity.unwrap_or(self.velocity);
-                if new_velocity != -current_check_velocity {
-                    self.next_velocity = Some(new_velocity);
+                match (action, keycode) {
+                    (Some(Action::Confirm), _) => {
+                        playback.paused = !playback.paused;
+                    }
+                    (Some(Action::MenuRight), _) => {
+                        // Frame-step, one tick at a time while paused
+                        if playback.paused && !playback.finished {
+                            self.advance_tick(ctx)?;
+                        }
+                        return Ok(());
+                    }
+                    (Some(Action::MenuUp | Action::VolumeUp), _) => {
+                        playback.speed = (playback.speed + 1).min(REPLAY_SPEEDS.len() - 1);
+                    }
+                    (Some(Action::MenuDown | Action::VolumeDown), _) => {
+                        playback.speed = playback.speed.saturating_sub(1);
+                    }
+                    (Some(Action::RestartReplay), _) => self.start_playback(),
+                    (Some(Action::Back), _) => {
+                        self.playback = None;
+                        self.mode = GameMode::Replays;
+                    }
+                    _ => return Ok(()), // Ignore other keys
+                }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
+            }
+            GameMode::GameOver => {
+                match action {
+                    Some(Action::MenuUp | Action::MenuDown) => {
+                        self.game_over_selection = 1 - self.game_over_selection;
+                    }
+                    Some(Action::Confirm) if self.game_over_selection == 0 => {
+                        // Retry with the same difficulty and seed setting
+                        self.mode = GameMode::Playing;
+                        self.reset_game_state();
+                    }
+                    Some(Action::Confirm | Action::Back) => {
+                        self.mode = self.menu_mode();
+                    }
+                    _ => return Ok(()), // Ignore other keys
                 }
+                self.audio.play_sfx(ctx, Sfx::MenuChange)?;
             }
         }
         Ok(())
     }
-} 
\ No newline at end of file
+}
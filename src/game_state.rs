@@ -1,25 +1,34 @@
+use crate::ai::{self, AiSkill};
+use crate::audio::{AudioManager, Music, Sfx};
+use crate::campaign::CampaignProgress;
 use crate::constants::*;
+use crate::editor::Editor;
+use crate::input::{key_name, Action, GamepadSlot, InputContext, KeyBindings, TurnQueue};
+use crate::level::{load_level_files, load_levels, Goal, Level};
+use crate::menu::{self, cycle, Menu, MenuEntry, MenuItem, MenuPage};
+use crate::replay::{best_path, load_replays, Ghost, Playback, Replay, ReplayFile};
+use crate::scores::{format_date, now_timestamp, ScoreBook, ScoreEntry};
+use crate::settings::Settings;
+use crate::simulation::{GameEvent, Simulation};
+use crate::storage;
+use crate::theme::Theme;
 use crate::types::*;
 use ggez::{
-    audio::{self, SoundSource, Source},
-    graphics::{Canvas, Color, DrawMode, MeshBuilder, Rect},
+    conf::{FullscreenType, WindowMode},
+    graphics::{Canvas, Color, DrawMode, DrawParam, Mesh, MeshBuilder, PxScale, Rect, Text},
+    input::keyboard::KeyCode,
+    mint,
+    winit::dpi::PhysicalPosition,
     Context,
     GameResult,
 };
 use nalgebra as na;
-use rand::rngs::ThreadRng;
-use rand::RngExt;
 use std::fs;
-use std::io::{Read, Write};
 use std::path::PathBuf;
 
 pub struct GameState {
-    pub snake_body: Vec<SnakeSegment>,
-    pub food: Food,
-    pub velocity: na::Vector2<f32>,
+    pub sim: Simulation,
     pub last_update: f32,
-    pub score: i32,
-    pub high_score: i32,
     pub mode: GameMode,
     pub window_width: f32,
     pub window_height: f32,
@@ -30,44 +39,87 @@ pub struct GameState {
     pub offset_x: f32, // Offset to center the game in case of extra window space
     pub offset_y: f32,
     pub difficulty: Difficulty,
-    pub move_time: f32, // Store the current move time based on difficulty
-    pub next_velocity: Option<na::Vector2<f32>>, // Buffer for the next move input
+    pub wall_rule: WallRule,
+    pub levels: Vec<Level>, // The open board first, then every loaded level file
+    pub level_index: usize, // Level the next game is played on
+    pub campaign: Vec<Level>, // Campaign levels in the order they are played
+    pub campaign_progress: CampaignProgress,
+    pub campaign_path: PathBuf,
+    pub campaign_index: Option<usize>, // Campaign level being played, None in endless play
+    pub campaign_selection: usize, // Highlighted row of the campaign screen
+    pub level_complete_selection: usize, // Highlighted entry of LEVEL_COMPLETE_OPTIONS
+    pub editor: Editor,
+    pub menu: Menu,
+    pub playtesting: bool, // Runs are played on the editor's level until the editor is left
+    pub play_mode: PlayMode,
+    pub run_category: String, // Score table of the run being played, fixed when it starts
+    pub input_queues: [TurnQueue; MAX_PLAYERS], // Turns pressed ahead, per player
+    pub best_of: u32, // Rounds in a versus match, one of MATCH_LENGTHS
+    pub round: u32,   // Versus round being played, from 1
+    pub round_wins: [u32; MAX_PLAYERS],
+    pub round_winner: Option<usize>, // Player who took the last round, None on a draw
+    pub coop_respawn: bool, // Co-op snakes come back after a delay instead of ending the run
+    pub ai_count: usize, // Computer snakes in a game against AI rivals, up to MAX_RIVALS
+    pub ai_skill: AiSkill,
+    pub idle_time: f32, // Seconds on the menu without input, the demo starts at DEMO_IDLE_TIME
+    pub demo: bool, // The menu shows an autopilot game in the background
+    pub autopilot: bool, // Hidden testing switch, the autopilot steers player one
+    pub assisted: bool, // The autopilot played some of this run, so it isn't scored
+    pub recording: Option<Replay>, // The run being played, saved as a replay once it ends
+    pub playback: Option<Playback>, // The replay being watched
+    pub replays: Vec<ReplayFile>, // Listed on the Replays screen, newest first
+    pub replay_selection: usize,
+    pub replays_dir: PathBuf,
+    pub ghost_enabled: bool,
+    pub ghost: Option<Ghost>, // The best run being raced, gone once its run is over
+    pub best_replays_dir: PathBuf, // One replay per score category and seed, never pruned
+    pub key_bindings: KeyBindings,
+    pub controls_page: usize, // Entry of CONTROLS_PAGES shown on the controls screen
+    pub controls_selection: usize, // Highlighted row of the controls page, the last resets
+    pub rebinding: Option<Action>, // Waiting for the next key press to bind to this action
+    pub controls_message: Option<String>, // Why the last key was refused, or what was reset
+    pub menu_message: Option<String>, // Why the last game picked on the menu didn't start
+    pub gamepads: Vec<GamepadSlot>, // Connected pads in the order they were found
+    pub fixed_seed: Option<u64>, // Seed every new game uses, random when None
+    pub seed_input: Option<String>, // Digits typed on the menu while entering a seed
+    pub last_seed: Option<u64>, // Seed of the most recent game, shown after it ends
+    pub pause_selection: usize, // Highlighted entry of PAUSE_OPTIONS
+    pub resume_countdown: f32, // Seconds left before the snake moves again, 0 when running
+    pub run_time: f32, // Real seconds the run has been played, pauses and countdowns left out
+    pub last_run: Option<RunSummary>, // Shown on the game over screen
+    pub game_over_selection: usize, // Highlighted entry of GAME_OVER_OPTIONS
     // Audio fields
-    pub menu_music: Source,
-    pub game_music: Source,
-    pub eat_sound: Source,
-    pub game_over_sound: Source,
-    pub menu_change_sound: Source,
-    pub music_volume: f32,
+    pub audio: AudioManager,
     pub music_speed: f32,
-    pub special_mode_music: Source,
-    pub high_score_path: PathBuf,
-}
-
-fn load_high_score(path: &PathBuf) -> i32 {
-    if let Ok(mut file) = fs::File::open(path) {
-        let mut contents = String::new();
-        if file.read_to_string(&mut contents).is_ok() {
-            if let Ok(score) = contents.trim().parse::<i32>() {
-                return score;
-            }
-        }
-    }
-    0 // Return 0 if file doesn't exist, can't be read, or content is invalid
+    pub scores: ScoreBook,
+    pub scores_path: PathBuf,
+    pub score_table_index: usize, // Category shown on the high score screen
+    pub theme: Theme,
+    pub grid_lines: bool,
+    pub show_fps: bool,
+    pub input_queue_length: usize, // Turns a player can queue, see queue_turn
+    pub window_vsync: bool, // What the window was made with, settings.vsync waits for a restart
+    pub settings: Settings, // Last saved copy, refreshed from the live fields by save_settings
+    pub settings_path: PathBuf,
 }
 
 impl GameState {
     pub fn new(ctx: &mut Context, is_bundle: bool) -> GameResult<GameState> {
         // Determine user data directory
         let mut data_dir = PathBuf::from(ctx.fs.user_data_dir());
-        data_dir.push("RustSnakeGame");
+        data_dir.push(DATA_DIR_NAME);
         if let Err(e) = fs::create_dir_all(&data_dir) {
             eprintln!("Failed to create data directory: {}", e);
         }
-        let high_score_path = data_dir.join("highscore.txt");
-        let high_score = load_high_score(&high_score_path);
+        let scores_path = data_dir.join("scores.json");
+        let scores = ScoreBook::load(&scores_path, &data_dir.join("highscore.txt"));
+        let settings_path = data_dir.join("settings.json");
+        let settings = Settings::load(&settings_path);
+        if let Err(e) = GameState::apply_window_settings(ctx, &settings) {
+            eprintln!("Failed to restore window mode: {}", e);
+        }
 
-        let (window_width, window_height) = GameState::get_window_size(ctx);
+        let (window_width, window_height) = ctx.gfx.drawable_size();
         let (boundary_width, boundary_height) =
             GameState::calculate_locked_boundary(window_width, window_height);
         let scale = boundary_width / REFERENCE_WIDTH;
@@ -79,49 +131,27 @@ impl GameState {
         let resource_prefix = if is_bundle { "/resources" } else { "" };
 
         // Load audio files using the determined prefix
-        let mut menu_music =
-            audio::Source::new(ctx, &format!("{}{}", resource_prefix, "/menu_music.mp3"))?;
-        let mut game_music =
-            audio::Source::new(ctx, &format!("{}{}", resource_prefix, "/game_music.wav"))?;
-        let mut eat_sound =
-            audio::Source::new(ctx, &format!("{}{}", resource_prefix, "/eat.ogg"))?;
-        let mut game_over_sound =
-            audio::Source::new(ctx, &format!("{}{}", resource_prefix, "/game_over.wav"))?;
-        let menu_change_sound = audio::Source::new(
-            ctx,
-            &format!("{}{}", resource_prefix, "/menu_option_change.wav"),
-        )?;
-        let mut special_mode_music = audio::Source::new(
-            ctx,
-            &format!("{}{}", resource_prefix, "/special_mode_music.mp3"),
-        )?;
-
-        // Set music to loop
-        menu_music.set_repeat(true);
-        game_music.set_repeat(true);
-        special_mode_music.set_repeat(true);
-        menu_music.set_volume(INITIAL_MUSIC_VOLUME);
-        game_music.set_volume(INITIAL_MUSIC_VOLUME);
-        eat_sound.set_volume(INITIAL_MUSIC_VOLUME);
-        game_over_sound.set_volume(INITIAL_MUSIC_VOLUME);
-        special_mode_music.set_volume(INITIAL_MUSIC_VOLUME);
+        let mut audio = AudioManager::new(ctx, resource_prefix)?;
+        audio.master_volume = settings.master_volume;
+        audio.music_volume = settings.music_volume;
+        audio.sfx_volume = settings.sfx_volume;
+        audio.muted = settings.muted;
+        audio.apply_volumes();
         // Play menu music initially
-        menu_music.play(ctx)?;
+        audio.play_music(ctx, Music::Menu)?;
+
+        let mut levels = vec![Level::open()];
+        levels.extend(load_levels(ctx, &format!("{}/levels", resource_prefix)));
+        // Levels saved from the editor come after the bundled ones
+        let user_levels_dir = data_dir.join("levels");
+        levels.extend(load_level_files(&user_levels_dir));
+        let campaign = load_levels(ctx, &format!("{}/campaign", resource_prefix));
+        let campaign_path = data_dir.join("campaign.json");
+        let campaign_progress = CampaignProgress::load(&campaign_path);
 
         let s = GameState {
-            snake_body: vec![SnakeSegment {
-                pos: na::Point2::new(
-                    ((boundary_width / 4.0) / scaled_snake_size).floor() * scaled_snake_size,
-                    ((boundary_height / 4.0) / scaled_snake_size).floor() * scaled_snake_size,
-                ),
-            }],
-            food: Food {
-                pos: GameState::get_random_food_position(boundary_width, boundary_height, scale),
-            },
-            velocity: na::Vector2::new(scaled_snake_size, 0.0),
+            sim: Simulation::new(Difficulty::Normal, WallRule::Solid, &levels[0], 1, 0),
             last_update: 0.0,
-            score: 0,
-            high_score,
             mode: GameMode::Menu,
             window_width,
             window_height,
@@ -131,34 +161,736 @@ impl GameState {
             scaled_snake_size,
             offset_x,
             offset_y,
-            difficulty: Difficulty::Normal, // Set default difficulty to Normal
-            move_time: NORMAL_MOVE_TIME,    // Set default move time to Normal speed
-            next_velocity: None,
+            difficulty: settings.difficulty,
+            wall_rule: settings.wall_rule,
+            levels,
+            level_index: 0,
+            campaign,
+            campaign_progress,
+            campaign_path,
+            campaign_index: None,
+            campaign_selection: 0,
+            level_complete_selection: 0,
+            editor: Editor::new(user_levels_dir),
+            menu: Menu::new(),
+            playtesting: false,
+            play_mode: PlayMode::Solo,
+            run_category: String::new(),
+            input_queues: Default::default(),
+            best_of: MATCH_LENGTHS[1],
+            round: 1,
+            round_wins: [0; MAX_PLAYERS],
+            round_winner: None,
+            coop_respawn: false,
+            ai_count: settings.ai_count,
+            ai_skill: settings.ai_skill,
+            idle_time: 0.0,
+            demo: false,
+            autopilot: false,
+            assisted: false,
+            recording: None,
+            playback: None,
+            replays: Vec::new(),
+            replay_selection: 0,
+            replays_dir: data_dir.join("replays"),
+            ghost_enabled: settings.ghost,
+            ghost: None,
+            best_replays_dir: data_dir.join("best_replays"),
+            key_bindings: settings.key_bindings.clone(),
+            controls_page: 0,
+            controls_selection: 0,
+            rebinding: None,
+            controls_message: None,
+            menu_message: None,
+            gamepads: Vec::new(),
+            fixed_seed: None,
+            seed_input: None,
+            last_seed: None,
+            pause_selection: 0,
+            resume_countdown: 0.0,
+            run_time: 0.0,
+            last_run: None,
+            game_over_selection: 0,
             // Initialize audio fields
-            menu_music,
-            game_music,
-            eat_sound,
-            game_over_sound,
-            menu_change_sound,
-            music_volume: INITIAL_MUSIC_VOLUME,
+            audio,
             music_speed: 1.0,
-            special_mode_music,
-            high_score_path,
+            scores,
+            scores_path,
+            score_table_index: 0,
+            theme: settings.theme,
+            grid_lines: settings.grid_lines,
+            show_fps: settings.show_fps,
+            input_queue_length: settings.input_queue_length,
+            window_vsync: settings.vsync,
+            settings,
+            settings_path,
         };
         Ok(s)
     }
 
-    pub fn save_high_score(&self) {
-        match fs::File::create(&self.high_score_path) {
-            Ok(mut file) => {
-                if let Err(e) = write!(file, "{}", self.high_score) {
-                    eprintln!("Failed to write high score to file: {}", e);
+    // The resizable window every mode shares, in windowed or borderless fullscreen form
+    pub fn window_mode(width: f32, height: f32, fullscreen: bool) -> WindowMode {
+        WindowMode::default()
+            .dimensions(width, height)
+            .min_dimensions(MIN_WINDOW_WIDTH, MIN_WINDOW_HEIGHT)
+            .resizable(true)
+            .transparent(true)
+            .fullscreen_type(if fullscreen {
+                FullscreenType::Desktop
+            } else {
+                FullscreenType::Windowed
+            })
+    }
+
+    // Restores the saved window size, position and fullscreen state
+    fn apply_window_settings(ctx: &mut Context, settings: &Settings) -> GameResult {
+        let window = settings.window;
+        let width = window.width.max(MIN_WINDOW_WIDTH);
+        let height = window.height.max(MIN_WINDOW_HEIGHT);
+        // ggez still has the size the window was made with, not where the player dragged it,
+        // so the whole mode is set rather than only the fullscreen type
+        ctx.gfx
+            .set_mode(GameState::window_mode(width, height, window.fullscreen))?;
+        if let (Some(x), Some(y), false) = (window.x, window.y, window.fullscreen) {
+            let _ = ctx.gfx.set_window_position(PhysicalPosition::new(x, y));
+        }
+        Ok(())
+    }
+
+    // Switches between the saved window and borderless fullscreen. A run in progress
+    // carries on, only the layout is worked out again for the new size.
+    pub fn set_fullscreen(&mut self, ctx: &mut Context, fullscreen: bool) {
+        if fullscreen == self.settings.window.fullscreen {
+            return;
+        }
+        // Keep the windowed size and position to come back to
+        self.capture_window_settings(ctx);
+        self.settings.window.fullscreen = fullscreen;
+        if let Err(e) = GameState::apply_window_settings(ctx, &self.settings) {
+            eprintln!("Failed to switch fullscreen: {}", e);
+            self.settings.window.fullscreen = !fullscreen;
+        }
+        self.handle_resize(ctx);
+    }
+
+    // F11 and Alt+Enter, remembered for the next start
+    pub fn toggle_fullscreen(&mut self, ctx: &mut Context) {
+        self.set_fullscreen(ctx, !self.settings.window.fullscreen);
+        self.save_settings();
+    }
+
+    // Copies the live values into the settings and writes them out
+    pub fn save_settings(&mut self) {
+        self.settings.master_volume = self.audio.master_volume;
+        self.settings.music_volume = self.audio.music_volume;
+        self.settings.sfx_volume = self.audio.sfx_volume;
+        self.settings.muted = self.audio.muted;
+        self.settings.difficulty = self.difficulty;
+        self.settings.wall_rule = self.wall_rule;
+        self.settings.ai_count = self.ai_count;
+        self.settings.ai_skill = self.ai_skill;
+        self.settings.ghost = self.ghost_enabled;
+        self.settings.key_bindings = self.key_bindings.clone();
+        self.settings.theme = self.theme;
+        self.settings.grid_lines = self.grid_lines;
+        self.settings.show_fps = self.show_fps;
+        self.settings.input_queue_length = self.input_queue_length;
+        self.settings.save(&self.settings_path);
+    }
+
+    // Remembers where the window is, only while windowed so fullscreen doesn't clobber it
+    pub fn capture_window_settings(&mut self, ctx: &Context) {
+        if self.settings.window.fullscreen {
+            return;
+        }
+        let (width, height) = ctx.gfx.drawable_size();
+        self.settings.window.width = width;
+        self.settings.window.height = height;
+        if let Ok(position) = ctx.gfx.window_position() {
+            self.settings.window.x = Some(position.x);
+            self.settings.window.y = Some(position.y);
+        }
+    }
+
+    // The level picked for endless play
+    pub fn level(&self) -> &Level {
+        &self.levels[self.level_index]
+    }
+
+    // The level the current run is played on, from the editor, campaign or endless
+    pub fn current_level(&self) -> &Level {
+        if self.playtesting {
+            return &self.editor.level;
+        }
+        match self.campaign_index {
+            Some(index) => &self.campaign[index],
+            None => self.level(),
+        }
+    }
+
+    // Where leaving a run leads, back to the editor or campaign screen if it came from there
+    pub fn menu_mode(&self) -> GameMode {
+        if self.playtesting {
+            GameMode::Editor
+        } else if self.campaign_index.is_some() {
+            GameMode::Campaign
+        } else {
+            GameMode::Menu
+        }
+    }
+
+    // Leaves seed entry, keeping the typed seed or dropping it. Keeping an empty entry
+    // goes back to a random seed per game.
+    pub fn finish_seed_input(&mut self, keep: bool) {
+        let input = self.seed_input.take().unwrap_or_default();
+        if keep {
+            self.fixed_seed = input.parse().ok();
+        }
+    }
+
+    // The rows of the open menu page, every option with its current value
+    pub fn menu_items(&self) -> Vec<MenuItem> {
+        match self.menu.page {
+            MenuPage::Main => vec![
+                MenuItem::link(MenuEntry::Open(MenuPage::Play), "Play"),
+                MenuItem::link(MenuEntry::Open(MenuPage::Settings), "Settings"),
+                MenuItem::link(MenuEntry::HighScores, "High Scores"),
+                MenuItem::link(MenuEntry::Replays, "Replays"),
+                MenuItem::link(MenuEntry::Quit, "Quit"),
+            ],
+            MenuPage::Play => {
+                let seed = match (&self.seed_input, self.fixed_seed) {
+                    (Some(input), _) => format!("{}_", input),
+                    (None, Some(seed)) => seed.to_string(),
+                    (None, None) => "Random".to_string(),
+                };
+                vec![
+                    MenuItem::link(MenuEntry::Start, "Start"),
+                    MenuItem::option(MenuEntry::Difficulty, "Difficulty", self.difficulty.name()),
+                    MenuItem::option(MenuEntry::Level, "Level", &self.level().name),
+                    MenuItem::option(MenuEntry::Walls, "Walls", self.wall_rule.name()),
+                    MenuItem::option(MenuEntry::Seed, "Seed", seed),
+                    MenuItem::option(MenuEntry::Ghost, "Ghost", on_off(self.ghost_enabled)),
+                    MenuItem::link(MenuEntry::Campaign, "Campaign"),
+                    MenuItem::link(MenuEntry::Open(MenuPage::Multiplayer), "Multiplayer"),
+                    MenuItem::link(MenuEntry::Editor, "Level Editor"),
+                    MenuItem::link(MenuEntry::Back, "Back"),
+                ]
+            }
+            MenuPage::Multiplayer => vec![
+                MenuItem::link(MenuEntry::Versus, "Versus"),
+                MenuItem::option(MenuEntry::BestOf, "Best of", self.best_of),
+                MenuItem::link(MenuEntry::Coop, "Co-op"),
+                MenuItem::option(MenuEntry::CoopRespawn, "Respawns", on_off(self.coop_respawn)),
+                MenuItem::link(MenuEntry::Rivals, "vs AI"),
+                MenuItem::option(MenuEntry::RivalCount, "Rivals", self.ai_count),
+                MenuItem::option(MenuEntry::RivalSkill, "AI Skill", self.ai_skill.name()),
+                MenuItem::link(MenuEntry::Back, "Back"),
+            ],
+            MenuPage::Settings => {
+                let percent = |volume: f32| format!("{:.0}%", volume * 100.0);
+                let master = percent(self.audio.master_volume);
+                let fullscreen = self.settings.window.fullscreen;
+                let mut vsync = on_off(self.settings.vsync).to_string();
+                if self.settings.vsync != self.window_vsync {
+                    vsync.push_str(" (after restart)");
                 }
+                vec![
+                    MenuItem::option(MenuEntry::Volume, "Volume", master),
+                    MenuItem::option(MenuEntry::Music, "Music", percent(self.audio.music_volume)),
+                    MenuItem::option(MenuEntry::Effects, "Effects", percent(self.audio.sfx_volume)),
+                    MenuItem::option(MenuEntry::Mute, "Mute", on_off(self.audio.muted)),
+                    MenuItem::option(MenuEntry::Fullscreen, "Fullscreen", on_off(fullscreen)),
+                    MenuItem::option(MenuEntry::Vsync, "VSync", vsync),
+                    MenuItem::option(MenuEntry::Theme, "Theme", self.theme.name()),
+                    MenuItem::option(MenuEntry::GridLines, "Grid Lines", on_off(self.grid_lines)),
+                    MenuItem::option(MenuEntry::ShowFps, "Show FPS", on_off(self.show_fps)),
+                    MenuItem::option(MenuEntry::InputQueue, "Turn Queue", self.input_queue_length),
+                    MenuItem::link(MenuEntry::Controls, "Key Bindings"),
+                    MenuItem::link(MenuEntry::Back, "Back"),
+                ]
+            }
+        }
+    }
+
+    // Enter or a click on the focused row, options step forward like Right
+    pub fn activate_menu_item(&mut self, ctx: &mut Context) {
+        let Some(item) = self.menu_items().into_iter().nth(self.menu.focus) else {
+            return;
+        };
+        self.menu_message = None;
+        match item.entry {
+            MenuEntry::Open(page) => self.menu.open(page),
+            MenuEntry::Back => {
+                self.menu_back();
+            }
+            MenuEntry::Quit => ctx.request_quit(),
+            MenuEntry::Start => {
+                self.play_mode = PlayMode::Solo;
+                self.campaign_index = None;
+                self.mode = GameMode::Playing;
+                self.reset_game_state();
+            }
+            MenuEntry::Level => self.mode = GameMode::LevelSelect,
+            MenuEntry::Seed => {
+                // Start typing a seed, prefilled with the current one
+                self.seed_input = Some(
+                    self.fixed_seed
+                        .map(|seed| seed.to_string())
+                        .unwrap_or_default(),
+                );
+            }
+            MenuEntry::Campaign => {
+                // Open the campaign on the first level not yet completed
+                self.campaign_selection = self
+                    .campaign
+                    .iter()
+                    .position(|level| self.campaign_progress.stars(&level.name) == 0)
+                    .unwrap_or(0);
+                self.mode = GameMode::Campaign;
+            }
+            MenuEntry::Editor => self.mode = GameMode::Editor,
+            MenuEntry::Versus => self.start_versus(),
+            MenuEntry::Coop => self.start_coop(),
+            MenuEntry::Rivals => self.start_rivals(),
+            MenuEntry::HighScores => {
+                // Open the tables on the selected difficulty
+                let category = self.menu_score_category();
+                self.score_table_index = self
+                    .score_categories()
+                    .iter()
+                    .position(|name| *name == category)
+                    .unwrap_or(0);
+                self.mode = GameMode::HighScores;
+            }
+            MenuEntry::Replays => self.open_replays(),
+            MenuEntry::Controls => {
+                self.controls_page = 0;
+                self.controls_selection = 0;
+                self.controls_message = None;
+                self.mode = GameMode::Controls;
+            }
+            _ => {
+                self.adjust_menu_item(ctx, 1);
+            }
+        }
+    }
+
+    // Left/Right on the focused row, false when it isn't an option
+    pub fn adjust_menu_item(&mut self, ctx: &mut Context, step: isize) -> bool {
+        let Some(item) = self.menu_items().into_iter().nth(self.menu.focus) else {
+            return false;
+        };
+        match item.entry {
+            MenuEntry::Difficulty => {
+                self.difficulty = cycle(&Difficulty::ALL, self.difficulty, step);
+            }
+            MenuEntry::Level => {
+                let count = self.levels.len() as isize;
+                self.level_index = (self.level_index as isize + step).rem_euclid(count) as usize;
             }
-            Err(e) => {
-                eprintln!("Failed to open high score file for writing: {}", e);
+            MenuEntry::Walls => {
+                self.wall_rule = cycle(&[WallRule::Solid, WallRule::Wrap], self.wall_rule, step);
             }
+            MenuEntry::Ghost => self.ghost_enabled = !self.ghost_enabled,
+            MenuEntry::BestOf => self.best_of = cycle(&MATCH_LENGTHS, self.best_of, step),
+            MenuEntry::CoopRespawn => self.coop_respawn = !self.coop_respawn,
+            MenuEntry::RivalCount => {
+                let counts: Vec<usize> = (1..=MAX_RIVALS).collect();
+                self.ai_count = cycle(&counts, self.ai_count, step);
+            }
+            MenuEntry::RivalSkill => self.ai_skill = cycle(&AiSkill::ALL, self.ai_skill, step),
+            MenuEntry::Volume => self.adjust_master_volume(0.1 * step as f32),
+            MenuEntry::Music | MenuEntry::Effects => {
+                let volume = match item.entry {
+                    MenuEntry::Music => &mut self.audio.music_volume,
+                    _ => &mut self.audio.sfx_volume,
+                };
+                *volume = (*volume + 0.1 * step as f32).clamp(0.0, 1.0);
+                self.audio.apply_volumes();
+            }
+            MenuEntry::Mute => self.audio.toggle_mute(),
+            MenuEntry::Fullscreen => self.set_fullscreen(ctx, !self.settings.window.fullscreen),
+            MenuEntry::Vsync => self.settings.vsync = !self.settings.vsync,
+            MenuEntry::Theme => self.theme = cycle(&Theme::ALL, self.theme, step),
+            MenuEntry::GridLines => self.grid_lines = !self.grid_lines,
+            MenuEntry::ShowFps => self.show_fps = !self.show_fps,
+            MenuEntry::InputQueue => {
+                let lengths: Vec<usize> = (1..=MAX_INPUT_QUEUE_LENGTH).collect();
+                self.input_queue_length = cycle(&lengths, self.input_queue_length, step);
+            }
+            _ => return false,
         }
+        self.save_settings();
+        true
+    }
+
+    // Back to the page above with the row that led here focused, false on the main page
+    pub fn menu_back(&mut self) -> bool {
+        let page = self.menu.page;
+        let Some(parent) = page.parent() else {
+            return false;
+        };
+        self.menu.open(parent);
+        self.menu.focus = self
+            .menu_items()
+            .iter()
+            .position(|item| item.entry == MenuEntry::Open(page))
+            .unwrap_or(0);
+        true
+    }
+
+    pub fn adjust_master_volume(&mut self, delta: f32) {
+        self.audio.master_volume = (self.audio.master_volume + delta).clamp(0.0, 1.0);
+        self.audio.apply_volumes();
+    }
+
+    // Saves the editor's level and puts it on the level select screen
+    pub fn save_editor_level(&mut self) {
+        if !self.editor.save() {
+            return;
+        }
+        let level = self.editor.level.clone();
+        // The open board at index 0 is never replaced
+        match self.levels.iter().skip(1).position(|known| known.name == level.name) {
+            Some(index) => self.levels[index + 1] = level,
+            None => self.levels.push(level),
+        }
+    }
+
+    // Shown in the HUD while a level has a goal
+    pub fn goal_progress(&self) -> Option<String> {
+        let sim = &self.sim;
+        Some(match sim.goal? {
+            Goal::Length(length) => format!("Length: {}/{}", sim.player().body.len(), length),
+            Goal::FoodWithin { food, seconds } => format!(
+                "Food: {}/{} ({:.0}s left)",
+                sim.player().food_eaten,
+                food,
+                (seconds - sim.elapsed).max(0.0)
+            ),
+            Goal::Survive(seconds) => {
+                format!("Survive: {:.0}/{:.0}s", sim.elapsed.min(seconds), seconds)
+            }
+        })
+    }
+
+    // Score, length and food of the run, summed over both snakes in co-op
+    pub fn run_totals(&self) -> (i32, usize, u32) {
+        let team = match self.play_mode {
+            PlayMode::Coop => &self.sim.snakes[..],
+            _ => &self.sim.snakes[..1],
+        };
+        team.iter().fold((0, 0, 0), |(score, length, food), snake| {
+            (
+                score + snake.score,
+                length + snake.body.len(),
+                food + snake.food_eaten,
+            )
+        })
+    }
+
+    // The player's place among every snake by score, ties sharing the better place
+    pub fn player_rank(&self) -> (usize, usize) {
+        let score = self.sim.player().score;
+        let ahead = self.sim.snakes.iter().filter(|snake| snake.score > score).count();
+        (ahead + 1, self.sim.snakes.len())
+    }
+
+    // This tick's move for every snake: the buffered keys for the players, the AI's
+    // choice for the computer snakes
+    pub fn tick_inputs(&mut self) -> Vec<Option<Direction>> {
+        let mut inputs: Vec<Option<Direction>> =
+            self.input_queues.iter_mut().map(TurnQueue::pop).collect();
+        if self.autopilot {
+            inputs[0] = ai::autopilot(&self.sim, 0);
+        }
+        if self.play_mode == PlayMode::Rivals {
+            inputs.truncate(1);
+            for index in 1..self.sim.snakes.len() {
+                inputs.push(ai::choose_direction(&self.sim, index, self.ai_skill));
+            }
+        }
+        inputs
+    }
+
+    // Keys mean game actions while a run is played, editor actions in the editor and menu
+    // actions everywhere else
+    pub fn input_context(&self) -> InputContext {
+        match self.mode {
+            GameMode::Playing => InputContext::Game,
+            GameMode::Editor => InputContext::Editor,
+            _ => InputContext::Menu,
+        }
+    }
+
+    // The actions listed on the open page of the controls screen, the editor's have their own
+    pub fn controls_actions(&self) -> Vec<Action> {
+        let editor_page = self.controls_page == 1;
+        Action::ALL
+            .into_iter()
+            .filter(|action| (action.context() == Some(InputContext::Editor)) == editor_page)
+            .collect()
+    }
+
+    // Binds the key pressed on the controls screen, unless another action already
+    // listening at the same time uses it
+    pub fn rebind(&mut self, action: Action, key: KeyCode) {
+        self.rebinding = None;
+        match self.key_bindings.conflict(action, key) {
+            Some(other) => {
+                self.controls_message =
+                    Some(format!("{} is already bound to {}", key_name(key), other.name()));
+            }
+            None => {
+                self.key_bindings.bind(action, key);
+                self.controls_message = None;
+                self.save_settings();
+            }
+        }
+    }
+
+    // Follows gamepads being plugged in and out. A new pad steers the first player without
+    // one, and losing a pad in the middle of a run pauses it.
+    pub fn sync_gamepads(&mut self, ctx: &Context) {
+        let connected: Vec<_> = ctx.gamepad.gamepads().map(|(id, _)| id).collect();
+        let count = self.gamepads.len();
+        self.gamepads.retain(|slot| connected.contains(&slot.id));
+        if self.gamepads.len() < count && self.mode == GameMode::Playing {
+            self.pause();
+        }
+        for id in connected {
+            if self.gamepads.iter().any(|slot| slot.id == id) {
+                continue;
+            }
+            let player = (0..MAX_PLAYERS)
+                .find(|&player| self.gamepads.iter().all(|slot| slot.player != player))
+                .unwrap_or(0);
+            let name = ctx.gamepad.gamepad(id).name().to_string();
+            self.gamepads.push(GamepadSlot::new(id, name, player));
+        }
+    }
+
+    // Queues a turn for the player's snake, see TurnQueue for which turns are kept
+    pub fn queue_turn(&mut self, player: usize, direction: Direction) {
+        let Some(snake) = self.sim.snakes.get(player) else {
+            return; // This player has no snake in the run
+        };
+        self.input_queues[player].push(direction, snake.direction, self.input_queue_length);
+    }
+
+    // The table the menu's current choices would play into, a solo run on the selected board
+    pub fn menu_score_category(&self) -> String {
+        let level = if self.level_index == 0 { None } else { Some(self.level().name.as_str()) };
+        score_category(self.difficulty, self.wall_rule, PlayMode::Solo, level)
+    }
+
+    // Every category worth browsing, the difficulties first even while empty
+    pub fn score_categories(&self) -> Vec<String> {
+        let difficulties = Difficulty::ALL;
+        let mut categories: Vec<String> = difficulties
+            .iter()
+            .map(|difficulty| difficulty.name().to_string())
+            .chain(
+                difficulties
+                    .iter()
+                    .map(|difficulty| format!("{} Wrap", difficulty.name())),
+            )
+            .collect();
+        for category in self.scores.tables.keys() {
+            if !categories.contains(category) {
+                categories.push(category.clone());
+            }
+        }
+        categories
+    }
+
+    // Best score in the table the menu's choices play into
+    pub fn high_score(&self) -> i32 {
+        self.scores.best(&self.menu_score_category())
+    }
+
+    // Whether the run just played belongs in the score tables. The campaign is rated in
+    // stars instead, and play-tests, versus and runs the autopilot helped with don't count.
+    fn run_counts(&self) -> bool {
+        self.run_totals().0 > 0
+            && self.campaign_index.is_none()
+            && !self.playtesting
+            && !self.assisted
+            && self.play_mode != PlayMode::Versus
+    }
+
+    // Adds the current run to its table, true when it set a new best
+    pub fn record_score(&mut self) -> bool {
+        if !self.run_counts() {
+            return false;
+        }
+        let (score, length, _) = self.run_totals();
+        let entry = ScoreEntry {
+            score,
+            length,
+            timestamp: now_timestamp(),
+            duration: self.sim.elapsed,
+            seed: self.sim.seed,
+        };
+        let rank = self.scores.insert(&self.run_category, entry);
+        self.scores.save(&self.scores_path);
+        rank == Some(0)
+    }
+
+    // Freeze the run and duck the music under the pause overlay
+    pub fn pause(&mut self) {
+        self.mode = GameMode::Paused;
+        self.pause_selection = 0;
+        self.resume_countdown = 0.0;
+        self.audio.set_ducked(true);
+    }
+
+    // Back to playing, the snake waits out a countdown before it moves
+    pub fn resume(&mut self) {
+        self.mode = GameMode::Playing;
+        self.resume_countdown = RESUME_COUNTDOWN;
+        self.audio.set_ducked(false);
+    }
+
+    // Leaves a paused run. Like restarting, giving up keeps the run out of the score tables.
+    pub fn quit_to_menu(&mut self) {
+        self.finish_recording();
+        self.mode = self.menu_mode();
+        self.audio.set_ducked(false);
+    }
+
+    pub fn end_run(&mut self, ctx: &mut Context, outcome: RunOutcome) -> GameResult {
+        // The ghost races the best run the score tables took, never an abandoned one
+        let replay = self.finish_recording();
+        let best_path = self.best_replay_path().filter(|_| self.run_counts());
+        if let (Some(replay), Some(path)) = (replay, best_path) {
+            replay.save_if_best(&path);
+        }
+        let mut new_high_score = self.record_score();
+        let (score, length, food_eaten) = self.run_totals();
+        let mut stars = 0;
+        if outcome == RunOutcome::GoalComplete {
+            stars = self
+                .current_level()
+                .stars(self.sim.elapsed, self.sim.player().food_eaten);
+            if self.campaign_index.is_some() {
+                let name = self.current_level().name.clone();
+                new_high_score = self.campaign_progress.record(&name, stars);
+                self.campaign_progress.save(&self.campaign_path);
+            }
+        }
+        self.last_run = Some(RunSummary {
+            score,
+            length,
+            difficulty: self.sim.difficulty,
+            wall_rule: self.sim.wall_rule,
+            time_survived: self.run_time,
+            food_eaten,
+            final_move_time: self.sim.move_time,
+            outcome,
+            level_name: self.current_level().name.clone(),
+            new_high_score,
+            stars,
+            rank: (self.play_mode == PlayMode::Rivals).then(|| self.player_rank()),
+            seed: self.sim.seed,
+        });
+        self.mode = if self.campaign_index.is_some() && outcome == RunOutcome::GoalComplete {
+            GameMode::LevelComplete
+        } else {
+            GameMode::GameOver
+        };
+        self.game_over_selection = 0;
+        self.level_complete_selection = 0;
+
+        // Stop game music on game over
+        self.audio.stop_music(ctx)?;
+        if outcome.is_win() {
+            self.audio.play_sfx(ctx, Sfx::BoardCleared)
+        } else {
+            self.audio.play_sfx(ctx, Sfx::GameOver)
+        }
+    }
+
+    // A fresh versus match on the selected level, difficulty and walls
+    pub fn start_versus(&mut self) {
+        self.play_mode = PlayMode::Versus;
+        self.campaign_index = None;
+        self.round = 1;
+        self.round_wins = [0; MAX_PLAYERS];
+        self.mode = GameMode::Playing;
+        self.reset_game_state();
+        self.require_room_for_players();
+    }
+
+    // A two-player run needs a snake for each player. When the level is too small or
+    // packed to place them all, the run is dropped before it starts.
+    fn require_room_for_players(&mut self) {
+        if self.sim.snakes.len() >= MAX_PLAYERS {
+            return;
+        }
+        self.recording = None;
+        self.mode = self.menu_mode();
+        self.menu_message = Some(format!(
+            "{} has no room for {} snakes",
+            self.current_level().name,
+            MAX_PLAYERS
+        ));
+    }
+
+    // Both players against the board on the selected level, difficulty and walls
+    pub fn start_coop(&mut self) {
+        self.play_mode = PlayMode::Coop;
+        self.campaign_index = None;
+        self.mode = GameMode::Playing;
+        self.reset_game_state();
+        self.require_room_for_players();
+    }
+
+    // The player against `ai_count` computer snakes on the selected level
+    pub fn start_rivals(&mut self) {
+        self.play_mode = PlayMode::Rivals;
+        self.campaign_index = None;
+        self.mode = GameMode::Playing;
+        self.reset_game_state();
+    }
+
+    // Rounds needed to take a best of `best_of` match
+    pub fn wins_needed(&self) -> u32 {
+        self.best_of / 2 + 1
+    }
+
+    // Settles a versus round. The last snake standing takes it; if both are still
+    // alive because the board filled up, the higher score does. Ties are draws.
+    pub fn end_round(&mut self, ctx: &mut Context) -> GameResult {
+        let alive: Vec<usize> = (0..self.sim.snakes.len())
+            .filter(|&index| self.sim.snakes[index].alive)
+            .collect();
+        self.round_winner = match alive.as_slice() {
+            [only] => Some(*only),
+            [] => None, // Both died on the same tick
+            _ => {
+                let (one, two) = (self.sim.snakes[0].score, self.sim.snakes[1].score);
+                match one.cmp(&two) {
+                    std::cmp::Ordering::Greater => Some(0),
+                    std::cmp::Ordering::Less => Some(1),
+                    std::cmp::Ordering::Equal => None,
+                }
+            }
+        };
+        if let Some(winner) = self.round_winner {
+            self.round_wins[winner] += 1;
+        }
+        self.finish_recording();
+
+        self.mode = if self.round_wins.iter().any(|&wins| wins >= self.wins_needed()) {
+            GameMode::MatchOver
+        } else {
+            GameMode::RoundOver
+        };
+        self.game_over_selection = 0;
+        self.audio.stop_music(ctx)?;
+        self.audio.play_sfx(ctx, Sfx::GameOver)
     }
 
     pub fn calculate_locked_boundary(window_width: f32, window_height: f32) -> (f32, f32) {
@@ -180,61 +912,204 @@ impl GameState {
         (boundary_width, boundary_height)
     }
 
-    pub fn get_random_food_position(
-        boundary_width: f32,
-        boundary_height: f32,
-        scale: f32,
-    ) -> na::Point2<f32> {
-        let mut rng: ThreadRng = rand::rng();
-        na::Point2::new(
-            (rng.random_range(0..(boundary_width / (REFERENCE_SNAKE_SIZE * scale)) as u32) as f32)
-                * (REFERENCE_SNAKE_SIZE * scale),
-            (rng.random_range(0..(boundary_height / (REFERENCE_SNAKE_SIZE * scale)) as u32) as f32)
-                * (REFERENCE_SNAKE_SIZE * scale),
-        )
-    }
-
     pub fn reset_game_state(&mut self) {
-        self.snake_body = vec![SnakeSegment {
-            pos: na::Point2::new(
-                ((self.boundary_width / 4.0) / self.scaled_snake_size).floor()
-                    * self.scaled_snake_size,
-                ((self.boundary_height / 4.0) / self.scaled_snake_size).floor()
-                    * self.scaled_snake_size,
+        self.finish_recording();
+        let seed = self.fixed_seed.unwrap_or_else(rand::random);
+        let (difficulty, wall_rule) = match self.campaign_index {
+            // Campaign levels bring their own speed and always have solid edges
+            Some(_) => (
+                self.current_level().difficulty.unwrap_or(self.difficulty),
+                WallRule::Solid,
             ),
-        }];
-        self.food.pos = GameState::get_random_food_position(
-            self.boundary_width,
-            self.boundary_height,
-            self.scale,
-        );
-        self.velocity = na::Vector2::new(self.scaled_snake_size, 0.0);
-        self.score = 0;
-
-        // Set move_time based on the selected difficulty
-        self.move_time = match self.difficulty {
-            Difficulty::Easy => EASY_MOVE_TIME,
-            Difficulty::Normal => NORMAL_MOVE_TIME,
-            Difficulty::Hard => HARD_MOVE_TIME,
-            Difficulty::Special => SPECIAL_START_MOVE_TIME,
+            None => (self.difficulty, self.wall_rule),
         };
-        // Set initial music speed based on difficulty
-        self.music_speed = match self.difficulty {
-            Difficulty::Easy => 0.8,
-            Difficulty::Normal => 1.0,
-            Difficulty::Hard => 1.2,
-            Difficulty::Special => 1.0,
+        let mut level = self.current_level().clone();
+        let snake_count = match self.play_mode {
+            PlayMode::Solo => 1,
+            PlayMode::Versus | PlayMode::Coop => {
+                // Two players play the level's layout, its target and goal are single player rules
+                level.target_score = None;
+                level.goal = None;
+                MAX_PLAYERS
+            }
+            PlayMode::Rivals => 1 + self.ai_count,
         };
+        self.sim = Simulation::new(difficulty, wall_rule, &level, snake_count, seed);
+        let board = (level.name != OPEN_BOARD_NAME).then_some(level.name.as_str());
+        self.run_category = score_category(difficulty, wall_rule, self.play_mode, board);
+        if self.play_mode == PlayMode::Coop && self.coop_respawn {
+            self.sim.respawn_delay = Some(COOP_RESPAWN_DELAY);
+        }
+        if self.play_mode == PlayMode::Rivals {
+            self.sim.pellet_lifetime = Some(PELLET_LIFETIME);
+        }
+        self.recording = Some(Replay::start(&self.sim, &level, self.play_mode));
+        // Only a best run on the same board is raced, the level may have been edited since
+        self.ghost = self
+            .best_replay_path()
+            .filter(|_| self.ghost_enabled)
+            .and_then(|path| storage::load_json::<Replay>(&path))
+            .filter(|best| best.level == level.to_text())
+            .and_then(Ghost::new);
+        self.last_seed = Some(self.sim.seed);
+        self.assisted = self.autopilot;
+        self.input_queues = Default::default();
+        self.last_update = 0.0;
+        self.resume_countdown = 0.0;
+        self.run_time = 0.0;
+
+        self.music_speed = music_speed(difficulty);
+    }
+
+    // Saves the replay of the run that just ended with the recent ones and hands it back,
+    // runs too short to have moved are dropped
+    pub fn finish_recording(&mut self) -> Option<Replay> {
+        let mut replay = self.recording.take()?;
+        if replay.ticks == 0 {
+            return None;
+        }
+        replay.duration = self.sim.elapsed;
+        replay.score = self.run_totals().0;
+        replay.save(&self.replays_dir);
+        Some(replay)
     }
 
-    pub fn get_window_size(ctx: &mut Context) -> (f32, f32) {
-        let canvas = Canvas::from_frame(ctx, Some(Color::BLACK));
-        let rect = canvas.screen_coordinates().unwrap();
-        (rect.w, rect.h)
+    // Where the best run with the current rules and seed is kept. Only solo runs that
+    // could set a high score have one.
+    fn best_replay_path(&self) -> Option<PathBuf> {
+        if self.play_mode != PlayMode::Solo || self.campaign_index.is_some() || self.playtesting {
+            return None;
+        }
+        Some(best_path(&self.best_replays_dir, &self.run_category, self.sim.seed))
     }
 
+    // Opens the Replays screen with every saved replay, newest first
+    pub fn open_replays(&mut self) {
+        self.replays = load_replays(&self.replays_dir);
+        self.replay_selection = 0;
+        self.mode = GameMode::Replays;
+    }
+
+    // Watches the highlighted replay from the start
+    pub fn start_playback(&mut self) {
+        let Some(file) = self.replays.get(self.replay_selection) else {
+            return;
+        };
+        match file.replay.simulation() {
+            Ok(sim) => {
+                self.sim = sim;
+                self.ghost = None;
+                self.play_mode = file.replay.play_mode;
+                let name = file.level_name.as_str();
+                let board = (name != OPEN_BOARD_NAME).then_some(name);
+                self.run_category =
+                    score_category(self.sim.difficulty, self.sim.wall_rule, self.play_mode, board);
+                self.campaign_index = None;
+                self.playback = Some(Playback::new(file.replay.clone()));
+                self.music_speed = music_speed(self.sim.difficulty);
+                self.last_update = 0.0;
+                self.mode = GameMode::ReplayPlayback;
+            }
+            Err(e) => eprintln!("Failed to play replay {:?}: {}", file.path, e),
+        }
+    }
+
+    // Deletes the highlighted replay file
+    pub fn delete_replay(&mut self) {
+        if self.replay_selection >= self.replays.len() {
+            return;
+        }
+        let file = self.replays.remove(self.replay_selection);
+        if let Err(e) = fs::remove_file(&file.path) {
+            eprintln!("Failed to delete replay {:?}: {}", file.path, e);
+        }
+        self.replay_selection = self.replay_selection.min(self.replays.len().saturating_sub(1));
+    }
+
+    // Keeps the game music for the run's difficulty playing
+    pub fn play_game_music(&mut self, ctx: &mut Context) -> GameResult {
+        match self.sim.difficulty {
+            Difficulty::Special => self.audio.play_music(ctx, Music::Special),
+            _ => {
+                self.audio.set_game_music_pitch(self.music_speed);
+                self.audio.play_music(ctx, Music::Game)
+            }
+        }
+    }
+
+    // One tick of the game on screen. A live run takes the keys and the AI's moves and
+    // is recorded; a replay feeds back the moves it recorded and only plays the sounds.
+    pub fn advance_tick(&mut self, ctx: &mut Context) -> GameResult {
+        let inputs = match self.playback.as_mut() {
+            Some(playback) => playback.next_inputs(self.sim.snakes.len()),
+            None => self.tick_inputs(),
+        };
+        if let Some(recording) = self.recording.as_mut() {
+            recording.record(&self.sim, &inputs);
+        }
+        let events = self.sim.step(&inputs);
+
+        if let Some(playback) = self.playback.as_mut() {
+            playback.finished = !self.sim.running || playback.tick >= playback.replay.ticks;
+            if events.iter().any(|event| matches!(event, GameEvent::AteFood(_))) {
+                self.audio.play_sfx(ctx, Sfx::Eat)?;
+            }
+            return Ok(());
+        }
+
+        let mut coop_death = None;
+        for event in events {
+            match event {
+                GameEvent::AteFood(_) => {
+                    // Play eat sound
+                    self.audio.play_sfx(ctx, Sfx::Eat)?;
+                }
+                GameEvent::SpeedChanged(_) => {
+                    // The tick interval is read back from the simulation each frame
+                }
+                GameEvent::Respawned(_) => {
+                    // The snake is simply drawn again
+                }
+                GameEvent::Died(_, cause) if self.play_mode == PlayMode::Coop => {
+                    // Both snakes can fall on one tick, the run ends once below
+                    coop_death.get_or_insert(cause);
+                }
+                GameEvent::Died(index, _) if index > 0 => {
+                    // A computer snake fell, its body is left as pellets
+                }
+                _ if self.play_mode == PlayMode::Versus => {
+                    // Rounds are settled below, once the whole tick is known
+                }
+                GameEvent::BoardCleared => self.end_run(ctx, RunOutcome::BoardCleared)?,
+                GameEvent::TargetReached => self.end_run(ctx, RunOutcome::TargetReached)?,
+                GameEvent::GoalComplete => self.end_run(ctx, RunOutcome::GoalComplete)?,
+                GameEvent::GoalFailed => self.end_run(ctx, RunOutcome::OutOfTime)?,
+                GameEvent::Died(_, cause) => self.end_run(ctx, RunOutcome::Died(cause))?,
+            }
+        }
+
+        let alive = self.sim.snakes.iter().filter(|snake| snake.alive).count();
+        if self.play_mode == PlayMode::Versus && (alive < 2 || !self.sim.running) {
+            self.end_round(ctx)?;
+        }
+        // A co-op death ends the run for both, unless the fallen can respawn
+        if let Some(cause) = coop_death {
+            if self.mode == GameMode::Playing
+                && (self.sim.respawn_delay.is_none() || !self.sim.running)
+            {
+                self.end_run(ctx, RunOutcome::Died(cause))?;
+            }
+        }
+        Ok(())
+    }
+
+    // Lays everything out for the window's current size. Positions are kept in cells, so
+    // this can run at any time without touching the game itself.
     pub fn handle_resize(&mut self, ctx: &mut Context) {
-        let (window_width, window_height) = GameState::get_window_size(ctx);
+        let (window_width, window_height) = ctx.gfx.drawable_size();
+        if window_width < 1.0 || window_height < 1.0 {
+            return; // Minimized, keep the last layout for when the window comes back
+        }
         self.window_width = window_width;
         self.window_height = window_height;
         let (boundary_width, boundary_height) =
@@ -242,45 +1117,1311 @@ impl GameState {
         self.boundary_width = boundary_width;
         self.boundary_height = boundary_height;
         self.scale = boundary_width / REFERENCE_WIDTH;
-        self.scaled_snake_size = REFERENCE_SNAKE_SIZE * self.scale;
+        // Levels can be any size, the largest square cells that still fit are used
+        let (width, height) = self.board_size();
+        let (fit_width, fit_height) = if self.mode == GameMode::Editor {
+            (boundary_width, boundary_height * EDITOR_BOARD_FRACTION) // Room for the panels
+        } else {
+            (boundary_width, boundary_height)
+        };
+        self.scaled_snake_size =
+            (fit_width / width as f32).min(fit_height / height as f32);
         self.offset_x = (window_width - boundary_width) / 2.0;
         self.offset_y = (window_height - boundary_height) / 2.0;
     }
 
-    pub fn scaled_rect(&self, pos: na::Point2<f32>) -> Rect {
+    // Size in cells of the board on screen, the editor's level while editing
+    pub fn board_size(&self) -> (i32, i32) {
+        if self.mode == GameMode::Editor {
+            (self.editor.level.width, self.editor.level.height)
+        } else {
+            (self.sim.width, self.sim.height)
+        }
+    }
+
+    // The board in window pixels, centered inside the 4:3 boundary
+    pub fn board_rect(&self) -> Rect {
+        let (width, height) = self.board_size();
+        let width = width as f32 * self.scaled_snake_size;
+        let height = height as f32 * self.scaled_snake_size;
+        Rect::new(
+            self.offset_x + (self.boundary_width - width) / 2.0,
+            self.offset_y + (self.boundary_height - height) / 2.0,
+            width,
+            height,
+        )
+    }
+
+    // The cell under a window position, None outside the board
+    pub fn cell_at(&self, x: f32, y: f32) -> Option<na::Point2<i32>> {
+        let board = self.board_rect();
+        if !board.contains(mint::Point2 { x, y }) {
+            return None;
+        }
+        let (width, height) = self.board_size();
+        Some(na::Point2::new(
+            (((x - board.x) / self.scaled_snake_size) as i32).min(width - 1),
+            (((y - board.y) / self.scaled_snake_size) as i32).min(height - 1),
+        ))
+    }
+
+    // The only place a grid cell is turned into window pixels
+    pub fn scaled_rect(&self, cell: na::Point2<i32>) -> Rect {
+        let board = self.board_rect();
         Rect::new(
-            self.offset_x + pos.x,
-            self.offset_y + pos.y,
+            board.x + cell.x as f32 * self.scaled_snake_size,
+            board.y + cell.y as f32 * self.scaled_snake_size,
             self.scaled_snake_size,
             self.scaled_snake_size,
         )
     }
 
-    pub fn draw_border(&self, mesh_builder: &mut MeshBuilder) {
-        let border_thickness = 5.0 * self.scale;
-        let rect = Rect::new(
-            self.offset_x,
-            self.offset_y,
-            self.boundary_width,
-            self.boundary_height,
+    // Counts the menu's idle time and, once the demo is on, plays it with the autopilot.
+    // A finished demo game is followed straight away by another.
+    pub fn update_demo(&mut self, delta: f32) {
+        if !self.demo {
+            // Typing a seed is slow, not idle
+            self.idle_time = if self.seed_input.is_some() {
+                0.0
+            } else {
+                self.idle_time + delta
+            };
+            if self.idle_time >= DEMO_IDLE_TIME {
+                self.start_demo();
+            }
+            return;
+        }
+        self.last_update += delta;
+        if self.last_update >= self.sim.move_time {
+            self.last_update = 0.0;
+            let input = ai::autopilot(&self.sim, 0);
+            self.sim.step(&[input]);
+            if !self.sim.running {
+                self.start_demo();
+            }
+        }
+    }
+
+    // A fresh demo game on the open board, the sim is replaced again when a run starts
+    fn start_demo(&mut self) {
+        self.demo = true;
+        self.ghost = None;
+        self.sim = Simulation::new(
+            Difficulty::Normal,
+            WallRule::Solid,
+            &self.levels[0],
+            1,
+            rand::random(),
+        );
+        self.last_update = 0.0;
+    }
+
+    pub fn stop_demo(&mut self) {
+        self.demo = false;
+        self.idle_time = 0.0;
+    }
+
+    // Snake, food, border and score HUD for the current run
+    pub fn draw_playfield(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.draw_board(ctx, canvas)?;
+        if self.play_mode == PlayMode::Versus {
+            return self.draw_versus_hud(ctx, canvas);
+        }
+        self.draw_hud(ctx, canvas)
+    }
+
+    // Walls, snakes, food and border of the simulation, without any text
+    pub fn draw_board(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        let mut mesh_builder = MeshBuilder::new();
+        let palette = self.theme.palette();
+
+        if self.grid_lines {
+            self.add_grid_lines(&mut mesh_builder)?;
+        }
+
+        // Draw the level's walls
+        for wall in self.sim.wall_cells() {
+            mesh_builder.rectangle(DrawMode::fill(), self.scaled_rect(wall), palette.wall)?;
+        }
+
+        // The ghost goes under everything else, faint enough to never be mistaken for a snake
+        if let Some(ghost) = &self.ghost {
+            let mut color = palette.snake_color(0);
+            color.a = 0.2;
+            for segment in &ghost.snake().body {
+                mesh_builder.rectangle(DrawMode::fill(), self.scaled_rect(segment.pos), color)?;
+            }
+        }
+
+        // Draw the snakes, the dead faded where they fell. Rivals that left pellets behind
+        // are gone, the player stays visible under them once the run is over.
+        for (index, snake) in self.sim.snakes.iter().enumerate() {
+            let mut color = palette.snake_color(index);
+            if !snake.alive {
+                if index > 0 && self.sim.pellet_lifetime.is_some() {
+                    continue;
+                }
+                color.a = 0.35;
+            }
+            for segment in &snake.body {
+                mesh_builder.rectangle(DrawMode::fill(), self.scaled_rect(segment.pos), color)?;
+            }
+        }
+
+        // Draw the food
+        mesh_builder.rectangle(
+            DrawMode::fill(),
+            self.scaled_rect(self.sim.food.pos),
+            palette.food,
+        )?;
+
+        // Pellets are smaller than food and fade out as they expire
+        let lifetime = self.sim.pellet_lifetime.unwrap_or(PELLET_LIFETIME);
+        for pellet in &self.sim.pellets {
+            let mut rect = self.scaled_rect(pellet.pos);
+            let inset = rect.w * 0.25;
+            rect.x += inset;
+            rect.y += inset;
+            rect.w -= inset * 2.0;
+            rect.h -= inset * 2.0;
+            let mut color = palette.food;
+            color.a = (pellet.time_left / lifetime).clamp(0.3, 1.0);
+            mesh_builder.rectangle(DrawMode::fill(), rect, color)?;
+        }
+
+        // Draw the game boundary
+        self.draw_border(&mut mesh_builder);
+
+        let mesh = Mesh::from_data(ctx, mesh_builder.build());
+        canvas.draw(&mesh, DrawParam::default());
+        Ok(())
+    }
+
+    // Score, best and objectives of a single player, solo, co-op or against the AI
+    fn draw_hud(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        // Draw score, with the player's place when racing computer snakes
+        let score_line = match self.play_mode {
+            PlayMode::Rivals => {
+                let (rank, count) = self.player_rank();
+                format!("Score: {} (Rank {}/{})", self.run_totals().0, rank, count)
+            }
+            _ => format!("Score: {}", self.run_totals().0),
+        };
+        let mut score_text = Text::new(score_line);
+        score_text.set_scale(PxScale::from(40.0 * self.scale));
+        canvas.draw(
+            &score_text,
+            DrawParam::default().dest(mint::Point2 {
+                x: (10.0 * self.scale) + self.offset_x,
+                y: (60.0 * self.scale) + self.offset_y,
+            }),
+        );
+        // The campaign is rated in stars, so its HUD names the level instead
+        let header = match self.campaign_index {
+            Some(index) => format!("Level {}: {}", index + 1, self.current_level().name),
+            None => format!("High Score: {}", self.scores.best(&self.run_category)),
+        };
+        let mut high_score_text = Text::new(header);
+        high_score_text.set_scale(PxScale::from(40.0 * self.scale));
+        canvas.draw(
+            &high_score_text,
+            DrawParam::default()
+                .dest(mint::Point2 {
+                    x: (10.0 * self.scale) + self.offset_x,
+                    y: (10.0 * self.scale) + self.offset_y,
+                })
+                .color(Color::from_rgb(0, 255, 0)),
         );
-        let _ = mesh_builder.rectangle(
-            DrawMode::stroke(border_thickness),
-            rect,
-            Color::from_rgb(255, 0, 0),
+        let objectives = self
+            .sim
+            .target_score
+            .map(|target| format!("Target: {}", target))
+            .into_iter()
+            .chain(self.goal_progress());
+        for (i, objective) in objectives.enumerate() {
+            let mut objective_text = Text::new(objective);
+            objective_text.set_scale(PxScale::from(40.0 * self.scale));
+            canvas.draw(
+                &objective_text,
+                DrawParam::default()
+                    .dest(mint::Point2 {
+                        x: (10.0 * self.scale) + self.offset_x,
+                        y: ((110.0 + 50.0 * i as f32) * self.scale) + self.offset_y,
+                    })
+                    .color(Color::from_rgb(255, 255, 0)),
+            );
+        }
+        if matches!(self.play_mode, PlayMode::Coop | PlayMode::Rivals) {
+            self.draw_snake_scores(ctx, canvas)?;
+        }
+        if self.autopilot {
+            let mut autopilot_text = Text::new("Autopilot");
+            autopilot_text.set_scale(PxScale::from(30.0 * self.scale));
+            canvas.draw(
+                &autopilot_text,
+                DrawParam::default()
+                    .dest(mint::Point2 {
+                        x: (10.0 * self.scale) + self.offset_x,
+                        y: self.boundary_height * 0.95 + self.offset_y,
+                    })
+                    .color(Color::from_rgb(180, 180, 180)),
+            );
+        }
+        Ok(())
+    }
+
+    // Every snake's score and length, right aligned in its own color
+    fn draw_snake_scores(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        let palette = self.theme.palette();
+        for (index, snake) in self.sim.snakes.iter().enumerate() {
+            let name = match (self.play_mode, index) {
+                (PlayMode::Rivals, 0) => "You".to_string(),
+                (PlayMode::Rivals, _) => format!("AI {}", index),
+                _ => format!("Player {}", index + 1),
+            };
+            let status = if snake.alive {
+                format!("{}: {} (length {})", name, snake.score, snake.body.len())
+            } else if self.sim.respawn_delay.is_some() {
+                format!("{}: back in {:.0}s", name, snake.respawn_timer.ceil())
+            } else {
+                format!("{}: {} (out)", name, snake.score)
+            };
+            let mut status_text = Text::new(status);
+            status_text.set_scale(PxScale::from(40.0 * self.scale));
+            let width = status_text.measure(ctx)?.x;
+            canvas.draw(
+                &status_text,
+                DrawParam::default()
+                    .dest(mint::Point2 {
+                        x: self.boundary_width - width - 10.0 * self.scale + self.offset_x,
+                        y: ((10.0 + 50.0 * index as f32) * self.scale) + self.offset_y,
+                    })
+                    .color(palette.snake_color(index)),
+            );
+        }
+        Ok(())
+    }
+
+    // Each player's score in their snake's color, with the match standing between them
+    fn draw_versus_hud(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        let palette = self.theme.palette();
+        for (index, snake) in self.sim.snakes.iter().enumerate() {
+            let mut score_text = Text::new(format!("Player {}: {}", index + 1, snake.score));
+            score_text.set_scale(PxScale::from(40.0 * self.scale));
+            // Player two is right aligned in the opposite corner
+            let x = if index == 0 {
+                10.0 * self.scale
+            } else {
+                self.boundary_width - score_text.measure(ctx)?.x - 10.0 * self.scale
+            };
+            canvas.draw(
+                &score_text,
+                DrawParam::default()
+                    .dest(mint::Point2 {
+                        x: x + self.offset_x,
+                        y: (10.0 * self.scale) + self.offset_y,
+                    })
+                    .color(palette.snake_color(index)),
+            );
+        }
+
+        let mut round_text = Text::new(format!(
+            "Round {}   {} - {}   (best of {})",
+            self.round, self.round_wins[0], self.round_wins[1], self.best_of
+        ));
+        round_text.set_scale(PxScale::from(30.0 * self.scale));
+        let width = round_text.measure(ctx)?.x;
+        canvas.draw(
+            &round_text,
+            DrawParam::default().dest(mint::Point2 {
+                x: (self.boundary_width - width) / 2.0 + self.offset_x,
+                y: (15.0 * self.scale) + self.offset_y,
+            }),
+        );
+        Ok(())
+    }
+
+    // Who took the round and the standing, over the board it was decided on
+    pub fn draw_round_over(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.draw_dim_overlay(ctx, canvas)?;
+        let palette = self.theme.palette();
+
+        let (title, color) = match self.round_winner {
+            Some(winner) => (
+                format!("Player {} takes round {}!", winner + 1, self.round),
+                palette.snake_color(winner),
+            ),
+            None => (format!("Round {} is a draw", self.round), Color::WHITE),
+        };
+        let mut title_text = Text::new(title);
+        title_text.set_scale(PxScale::from(70.0 * self.scale));
+        let width = title_text.measure(ctx)?.x;
+        canvas.draw(
+            &title_text,
+            DrawParam::default()
+                .dest(mint::Point2 {
+                    x: (self.boundary_width - width) / 2.0 + self.offset_x,
+                    y: self.boundary_height * 0.3 + self.offset_y,
+                })
+                .color(color),
+        );
+
+        let mut lines_text = Text::new(format!(
+            "{} - {}   (first to {})\n\nEnter for the next round, ESC to quit",
+            self.round_wins[0],
+            self.round_wins[1],
+            self.wins_needed()
+        ));
+        lines_text.set_scale(PxScale::from(40.0 * self.scale));
+        let width = lines_text.measure(ctx)?.x;
+        canvas.draw(
+            &lines_text,
+            DrawParam::default().dest(mint::Point2 {
+                x: (self.boundary_width - width) / 2.0 + self.offset_x,
+                y: self.boundary_height * 0.45 + self.offset_y,
+            }),
+        );
+        Ok(())
+    }
+
+    // The results screen at the end of a versus match
+    pub fn draw_match_over(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.draw_dim_overlay(ctx, canvas)?;
+        let palette = self.theme.palette();
+        let winner = if self.round_wins[0] >= self.round_wins[1] { 0 } else { 1 };
+
+        let mut title_text = Text::new(format!("Player {} Wins the Match!", winner + 1));
+        title_text.set_scale(PxScale::from(80.0 * self.scale));
+        let width = title_text.measure(ctx)?.x;
+        canvas.draw(
+            &title_text,
+            DrawParam::default()
+                .dest(mint::Point2 {
+                    x: (self.boundary_width - width) / 2.0 + self.offset_x,
+                    y: self.boundary_height * 0.2 + self.offset_y,
+                })
+                .color(palette.snake_color(winner)),
+        );
+
+        let mut summary_text = Text::new(format!(
+            "Rounds: {} - {}\nBest of {}, {} rounds played\nLevel: {} ({} walls)\nDifficulty: {}",
+            self.round_wins[0],
+            self.round_wins[1],
+            self.best_of,
+            self.round,
+            self.current_level().name,
+            self.sim.wall_rule.name(),
+            self.sim.difficulty.name()
+        ));
+        summary_text.set_scale(PxScale::from(40.0 * self.scale));
+        let width = summary_text.measure(ctx)?.x;
+        canvas.draw(
+            &summary_text,
+            DrawParam::default().dest(mint::Point2 {
+                x: (self.boundary_width - width) / 2.0 + self.offset_x,
+                y: self.boundary_height * 0.36 + self.offset_y,
+            }),
+        );
+
+        self.draw_options(ctx, canvas, &MATCH_OVER_OPTIONS, self.game_over_selection, 0.65)?;
+        Ok(())
+    }
+
+    // The open menu page: its title, a status line and the rows centred below them
+    pub fn draw_menu(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        let title = self.menu.page.title();
+        self.draw_centered_text(ctx, canvas, title, 80.0, 0.08, Color::from_rgb(0, 255, 0))?;
+
+        let mut status = format!("High Score: {}", self.high_score());
+        if let Some(last_seed) = self.last_seed {
+            status.push_str(&format!("      Last Game Seed: {}", last_seed));
+        }
+        let grey = Color::from_rgb(180, 180, 180);
+        self.draw_centered_text(ctx, canvas, &status, 30.0, 0.19, grey)?;
+
+        for (i, (text, rect)) in self.menu_layout(ctx)?.iter().enumerate() {
+            let color = if i == self.menu.focus {
+                Color::from_rgb(255, 255, 0) // Yellow for the focused row
+            } else {
+                Color::WHITE
+            };
+            canvas.draw(text, DrawParam::default().dest(rect.point()).color(color));
+        }
+
+        if let Some(message) = &self.menu_message {
+            let red = Color::from_rgb(255, 120, 120);
+            return self.draw_centered_text(ctx, canvas, message, 28.0, 0.93, red);
+        }
+        let hint = if self.seed_input.is_some() {
+            "Type a seed, Enter or click to set it, ESC to cancel"
+        } else if self.menu.page == MenuPage::Settings {
+            "F11 or Alt+Enter switches fullscreen on any screen"
+        } else {
+            "Arrows or mouse to choose, Enter or click to select, Left/Right to change"
+        };
+        self.draw_centered_text(ctx, canvas, hint, 28.0, 0.93, grey)
+    }
+
+    // Rows of the open menu page where they are drawn, shared with mouse hit-testing
+    pub fn menu_layout(&self, ctx: &Context) -> GameResult<Vec<(Text, Rect)>> {
+        let center = mint::Point2 {
+            x: self.boundary_width * 0.5 + self.offset_x,
+            y: self.boundary_height * 0.58 + self.offset_y,
+        };
+        menu::layout(ctx, &self.menu_items(), self.menu.focus, self.scale, center)
+    }
+
+    // A line of text centred across the play area at a fraction of its height
+    pub fn draw_centered_text(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        line: &str,
+        size: f32,
+        y: f32,
+        color: Color,
+    ) -> GameResult {
+        let mut text = Text::new(line);
+        text.set_scale(PxScale::from(size * self.scale));
+        let width = text.measure(ctx)?.x;
+        canvas.draw(
+            &text,
+            DrawParam::default()
+                .dest(mint::Point2 {
+                    x: (self.boundary_width - width) / 2.0 + self.offset_x,
+                    y: self.boundary_height * y + self.offset_y,
+                })
+                .color(color),
         );
+        Ok(())
     }
 
-    pub fn check_border_collisions(&self) -> bool {
-        let head_pos = self.snake_body[0].pos;
+    // Darkens everything drawn so far so text on top stays readable
+    pub fn draw_dim_overlay(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        let overlay = Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(0.0, 0.0, self.window_width, self.window_height),
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        )?;
+        canvas.draw(&overlay, DrawParam::default());
+        Ok(())
+    }
 
-        if head_pos.x < 0.0 - EPSILON
-            || head_pos.y < 0.0 - EPSILON
-            || head_pos.x + self.scaled_snake_size > self.boundary_width + EPSILON
-            || head_pos.y + self.scaled_snake_size > self.boundary_height + EPSILON
-        {
-            return true;
+    // A vertical list of options starting at `top` (a fraction of the boundary height),
+    // each one centred across the play area
+    pub fn draw_options(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        options: &[impl AsRef<str>],
+        selection: usize,
+        top: f32,
+    ) -> GameResult {
+        let rows = menu::stack(
+            ctx,
+            options,
+            50.0 * self.scale,
+            self.boundary_width * 0.5 + self.offset_x,
+            self.boundary_height * top + self.offset_y,
+        )?;
+        for (i, (text, rect)) in rows.iter().enumerate() {
+            let color = if i == selection {
+                Color::from_rgb(255, 255, 0) // Yellow for selected
+            } else {
+                Color::WHITE
+            };
+            canvas.draw(text, DrawParam::default().dest(rect.point()).color(color));
         }
-        false
+        Ok(())
+    }
+
+    // Translucent layer over the frozen board with the pause options
+    pub fn draw_pause_overlay(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.draw_dim_overlay(ctx, canvas)?;
+
+        self.draw_centered_text(ctx, canvas, "Paused", 80.0, 0.3, Color::WHITE)?;
+
+        self.draw_options(ctx, canvas, &PAUSE_OPTIONS, self.pause_selection, 0.45)?;
+        Ok(())
     }
-} 
\ No newline at end of file
+
+    // Summary of the run that just ended, over the board it ended on
+    pub fn draw_game_over(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.draw_dim_overlay(ctx, canvas)?;
+        let Some(run) = &self.last_run else {
+            return Ok(());
+        };
+
+        let (title, title_color) = match run.outcome {
+            RunOutcome::BoardCleared => ("Board Cleared! You Win!", Color::from_rgb(255, 215, 0)),
+            RunOutcome::TargetReached | RunOutcome::GoalComplete => {
+                ("Level Complete!", Color::from_rgb(255, 215, 0))
+            }
+            RunOutcome::OutOfTime => ("Out of Time", Color::from_rgb(255, 0, 0)),
+            RunOutcome::Died(_) => ("Game Over", Color::from_rgb(255, 0, 0)),
+        };
+        self.draw_centered_text(ctx, canvas, title, 70.0, 0.08, title_color)?;
+
+        let cause = match run.outcome {
+            RunOutcome::Died(DeathCause::Wall) => "Hit the wall".to_string(),
+            RunOutcome::Died(DeathCause::SelfCollision) => "Ran into yourself".to_string(),
+            RunOutcome::Died(DeathCause::Snake) => "Ran into another snake".to_string(),
+            RunOutcome::Died(DeathCause::HeadOn) => "Crashed head-on".to_string(),
+            RunOutcome::BoardCleared => format!("Filled the board (+{} bonus)", BOARD_CLEAR_BONUS),
+            RunOutcome::TargetReached => "Reached the target score".to_string(),
+            RunOutcome::GoalComplete => match self.sim.goal {
+                Some(goal) => format!("Completed: {}", goal.describe()),
+                None => "Completed the goal".to_string(),
+            },
+            RunOutcome::OutOfTime => "Ran out of time".to_string(),
+        };
+        let minutes = (run.time_survived / 60.0).floor() as i32;
+        let seconds = run.time_survived % 60.0;
+        let score_line = match run.rank {
+            Some((rank, count)) => format!("Score: {} (Rank {} of {})", run.score, rank, count),
+            None => format!("Score: {}", run.score),
+        };
+        let mut lines = vec![
+            score_line,
+            format!("Length: {}", run.length),
+            format!("Difficulty: {}", run.difficulty.name()),
+            format!("Level: {} ({} walls)", run.level_name, run.wall_rule.name()),
+            format!("Time Survived: {}:{:04.1}", minutes, seconds),
+            format!("Food per Minute: {:.1}", run.food_per_minute()),
+        ];
+        if run.difficulty == Difficulty::Special {
+            lines.push(format!("Final Move Time: {:.3}s", run.final_move_time));
+        }
+        lines.push(format!("Cause: {}", cause));
+        lines.push(format!("Seed: {}", run.seed));
+
+        self.draw_centered_text(ctx, canvas, &lines.join("\n"), 38.0, 0.2, Color::WHITE)?;
+
+        if run.new_high_score {
+            self.draw_centered_text(
+                ctx,
+                canvas,
+                "New High Score!",
+                50.0,
+                0.62,
+                Color::from_rgb(0, 255, 0),
+            )?;
+        }
+
+        self.draw_options(ctx, canvas, &GAME_OVER_OPTIONS, self.game_over_selection, 0.72)?;
+        Ok(())
+    }
+
+    // One score table at a time, Left/Right flips between categories
+    pub fn draw_high_scores(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        let categories = self.score_categories();
+        let category = &categories[self.score_table_index.min(categories.len() - 1)];
+
+        self.draw_centered_text(
+            ctx,
+            canvas,
+            &format!("<  High Scores: {}  >", category),
+            60.0,
+            0.06,
+            Color::from_rgb(0, 255, 0),
+        )?;
+
+        // Columns are drawn separately so they line up without a monospace font
+        let columns: [(&str, f32); 6] = [
+            ("#", 0.08),
+            ("Score", 0.15),
+            ("Length", 0.3),
+            ("Time", 0.45),
+            ("Date", 0.58),
+            ("Seed", 0.78),
+        ];
+        let table = self.scores.table(category);
+        for (column, (header, x)) in columns.iter().enumerate() {
+            let mut cells = vec![header.to_string()];
+            for (rank, entry) in table.iter().enumerate() {
+                cells.push(match column {
+                    0 => format!("{}", rank + 1),
+                    1 => format!("{}", entry.score),
+                    2 => format!("{}", entry.length),
+                    3 => format!(
+                        "{}:{:02}",
+                        (entry.duration / 60.0) as i32,
+                        (entry.duration % 60.0) as i32
+                    ),
+                    4 => format_date(entry.timestamp),
+                    _ => format!("{}", entry.seed),
+                });
+            }
+            let mut column_text = Text::new(cells.join("\n"));
+            column_text.set_scale(PxScale::from(36.0 * self.scale));
+            canvas.draw(
+                &column_text,
+                DrawParam::default().dest(mint::Point2 {
+                    x: self.boundary_width * x + self.offset_x,
+                    y: self.boundary_height * 0.18 + self.offset_y,
+                }),
+            );
+        }
+
+        if table.is_empty() {
+            self.draw_centered_text(
+                ctx,
+                canvas,
+                "No scores yet",
+                40.0,
+                0.4,
+                Color::from_rgb(180, 180, 180),
+            )?;
+        }
+
+        self.draw_centered_text(
+            ctx,
+            canvas,
+            "Left/Right to switch table, ESC to go back",
+            30.0,
+            0.92,
+            Color::from_rgb(180, 180, 180),
+        )
+    }
+
+    // Saved replays newest first, a window of REPLAY_LIST_ROWS follows the selection
+    pub fn draw_replays(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.draw_centered_text(ctx, canvas, "Replays", 60.0, 0.06, Color::from_rgb(0, 255, 0))?;
+
+        let first = self
+            .replay_selection
+            .saturating_sub(REPLAY_LIST_ROWS / 2)
+            .min(self.replays.len().saturating_sub(REPLAY_LIST_ROWS));
+        let columns: [(&str, f32); 6] = [
+            ("Date", 0.06),
+            ("Mode", 0.26),
+            ("Level", 0.38),
+            ("Difficulty", 0.6),
+            ("Score", 0.75),
+            ("Time", 0.86),
+        ];
+        for (column, (header, x)) in columns.iter().enumerate() {
+            let mut header_text = Text::new(*header);
+            header_text.set_scale(PxScale::from(36.0 * self.scale));
+            canvas.draw(
+                &header_text,
+                DrawParam::default().dest(mint::Point2 {
+                    x: self.boundary_width * x + self.offset_x,
+                    y: self.boundary_height * 0.16 + self.offset_y,
+                }),
+            );
+
+            // Cells are drawn one by one so the selected row can be highlighted
+            for (row, file) in self.replays.iter().enumerate().skip(first).take(REPLAY_LIST_ROWS) {
+                let replay = &file.replay;
+                let cell = match column {
+                    0 => format_date(replay.timestamp),
+                    1 => replay.play_mode.name().to_string(),
+                    2 => file.level_name.clone(),
+                    3 => replay.difficulty.name().to_string(),
+                    4 => format!("{}", replay.score),
+                    _ => format!(
+                        "{}:{:02}",
+                        (replay.duration / 60.0) as i32,
+                        (replay.duration % 60.0) as i32
+                    ),
+                };
+                let color = if row == self.replay_selection {
+                    Color::from_rgb(255, 255, 0)
+                } else {
+                    Color::WHITE
+                };
+                let mut cell_text = Text::new(cell);
+                cell_text.set_scale(PxScale::from(32.0 * self.scale));
+                canvas.draw(
+                    &cell_text,
+                    DrawParam::default()
+                        .dest(mint::Point2 {
+                            x: self.boundary_width * x + self.offset_x,
+                            y: self.boundary_height * (0.22 + 0.055 * (row - first) as f32)
+                                + self.offset_y,
+                        })
+                        .color(color),
+                );
+            }
+        }
+
+        if self.replays.is_empty() {
+            self.draw_centered_text(
+                ctx,
+                canvas,
+                "No replays yet, every run you play is recorded",
+                40.0,
+                0.4,
+                Color::from_rgb(180, 180, 180),
+            )?;
+        }
+
+        self.draw_centered_text(
+            ctx,
+            canvas,
+            "Up/Down to choose, Enter to watch, Delete to remove, ESC to go back",
+            30.0,
+            0.92,
+            Color::from_rgb(180, 180, 180),
+        )
+    }
+
+    // Speed, progress and controls along the bottom while a replay plays
+    pub fn draw_playback_overlay(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        let Some(playback) = &self.playback else {
+            return Ok(());
+        };
+        let state = if playback.finished {
+            "Finished".to_string()
+        } else if playback.paused {
+            "Paused".to_string()
+        } else {
+            format!("{}x", REPLAY_SPEEDS[playback.speed])
+        };
+        let lines = [
+            format!("Replay  {}  tick {}/{}", state, playback.tick, playback.replay.ticks),
+            "Space pause, Right step, Up/Down speed, R restart, ESC back".to_string(),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            let mut line_text = Text::new(line.as_str());
+            line_text.set_scale(PxScale::from(30.0 * self.scale));
+            let width = line_text.measure(ctx)?.x;
+            canvas.draw(
+                &line_text,
+                DrawParam::default()
+                    .dest(mint::Point2 {
+                        x: (self.boundary_width - width) / 2.0 + self.offset_x,
+                        y: self.boundary_height * (0.88 + 0.05 * i as f32) + self.offset_y,
+                    })
+                    .color(Color::from_rgb(180, 180, 180)),
+            );
+        }
+        Ok(())
+    }
+
+    // Every action of the open page with its keys. Game actions are on the left and menu
+    // actions on the right, the editor's page fills the left column first.
+    pub fn draw_controls(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        let title = format!("<  Controls: {}  >", CONTROLS_PAGES[self.controls_page]);
+        self.draw_centered_text(ctx, canvas, &title, 60.0, 0.06, Color::from_rgb(0, 255, 0))?;
+
+        let actions = self.controls_actions();
+        let mut rows = [0, 0]; // Rows used so far in each column
+        for (i, action) in actions.iter().enumerate() {
+            let column = match action.context() {
+                Some(InputContext::Menu) => 1,
+                Some(InputContext::Editor) if i >= actions.len().div_ceil(2) => 1,
+                _ => 0,
+            };
+            let keys = if self.rebinding == Some(*action) {
+                "Press a key...".to_string()
+            } else {
+                self.key_bindings.describe(*action)
+            };
+            let color = if i == self.controls_selection {
+                Color::from_rgb(255, 255, 0)
+            } else {
+                Color::WHITE
+            };
+            let x = 0.05 + 0.47 * column as f32;
+            let y = 0.17 + 0.045 * rows[column] as f32;
+            rows[column] += 1;
+            for (text, dx) in [(action.name().to_string(), 0.0), (keys, 0.2)] {
+                let mut cell_text = Text::new(text);
+                cell_text.set_scale(PxScale::from(32.0 * self.scale));
+                canvas.draw(
+                    &cell_text,
+                    DrawParam::default()
+                        .dest(mint::Point2 {
+                            x: self.boundary_width * (x + dx) + self.offset_x,
+                            y: self.boundary_height * y + self.offset_y,
+                        })
+                        .color(color),
+                );
+            }
+        }
+
+        // Gamepads are listed under the game actions
+        let mut pad_lines: Vec<String> = self
+            .gamepads
+            .iter()
+            .map(|slot| format!("{}: Player {}", slot.name, slot.player + 1))
+            .collect();
+        if pad_lines.is_empty() {
+            pad_lines.push("No gamepad connected".to_string());
+        } else {
+            pad_lines.push("Select on a pad switches its player".to_string());
+        }
+        for (i, line) in pad_lines.iter().enumerate() {
+            let mut pad_text = Text::new(line.as_str());
+            pad_text.set_scale(PxScale::from(28.0 * self.scale));
+            canvas.draw(
+                &pad_text,
+                DrawParam::default()
+                    .dest(mint::Point2 {
+                        x: self.boundary_width * 0.05 + self.offset_x,
+                        y: self.boundary_height * (0.73 + 0.045 * i as f32) + self.offset_y,
+                    })
+                    .color(Color::from_rgb(180, 180, 180)),
+            );
+        }
+
+        let reset_color = if self.controls_selection == actions.len() {
+            Color::from_rgb(255, 255, 0)
+        } else {
+            Color::WHITE
+        };
+        let mut reset_text = Text::new("Reset to defaults");
+        reset_text.set_scale(PxScale::from(40.0 * self.scale));
+        canvas.draw(
+            &reset_text,
+            DrawParam::default()
+                .dest(mint::Point2 {
+                    x: self.boundary_width * 0.52 + self.offset_x,
+                    y: self.boundary_height * 0.8 + self.offset_y,
+                })
+                .color(reset_color),
+        );
+
+        if let Some(message) = &self.controls_message {
+            self.draw_centered_text(
+                ctx,
+                canvas,
+                message.as_str(),
+                30.0,
+                0.86,
+                Color::from_rgb(255, 120, 120),
+            )?;
+        }
+
+        let hint = if self.rebinding.is_some() {
+            "Press the new key, ESC to cancel"
+        } else {
+            "Left/Right: page   Up/Down: choose   Enter: rebind   ESC: back"
+        };
+        self.draw_centered_text(ctx, canvas, hint, 30.0, 0.92, Color::from_rgb(180, 180, 180))
+    }
+
+    // Every loaded level, the highlighted one is played on Enter
+    pub fn draw_level_select(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.draw_centered_text(ctx, canvas, "Select Level", 70.0, 0.1, Color::WHITE)?;
+
+        let rows: Vec<String> = self
+            .levels
+            .iter()
+            .map(|level| match level.target_score {
+                Some(target) => {
+                    format!("{} ({}x{}, target {})", level.name, level.width, level.height, target)
+                }
+                None => format!("{} ({}x{})", level.name, level.width, level.height),
+            })
+            .collect();
+        self.draw_options(ctx, canvas, &rows, self.level_index, 0.25)?;
+
+        self.draw_centered_text(
+            ctx,
+            canvas,
+            "Up/Down to choose, Enter to play, ESC to go back",
+            30.0,
+            0.92,
+            Color::from_rgb(180, 180, 180),
+        )
+    }
+
+    // Campaign levels in order with their goal, speed and best rating
+    pub fn draw_campaign(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.draw_centered_text(ctx, canvas, "Campaign", 70.0, 0.06, Color::WHITE)?;
+
+        if self.campaign.is_empty() {
+            self.draw_centered_text(
+                ctx,
+                canvas,
+                "No campaign levels found",
+                40.0,
+                0.4,
+                Color::from_rgb(180, 180, 180),
+            )?;
+            return Ok(());
+        }
+
+        let mut mesh_builder = MeshBuilder::new();
+        for (i, level) in self.campaign.iter().enumerate() {
+            let y = self.boundary_height * (0.2 + 0.08 * i as f32) + self.offset_y;
+            let unlocked = self.campaign_progress.is_unlocked(&self.campaign, i);
+            let color = if i == self.campaign_selection {
+                Color::from_rgb(255, 255, 0) // Yellow for selected
+            } else if unlocked {
+                Color::WHITE
+            } else {
+                Color::from_rgb(100, 100, 100) // Grey until the level before is done
+            };
+
+            let mut name_text = Text::new(format!("{}. {}", i + 1, level.name));
+            name_text.set_scale(PxScale::from(45.0 * self.scale));
+            canvas.draw(
+                &name_text,
+                DrawParam::default()
+                    .dest(mint::Point2 {
+                        x: self.boundary_width * 0.08 + self.offset_x,
+                        y,
+                    })
+                    .color(color),
+            );
+
+            let detail = if unlocked {
+                let goal = level
+                    .goal
+                    .map_or("Survive".to_string(), |goal| goal.describe());
+                let difficulty = level.difficulty.unwrap_or(self.difficulty);
+                format!("{} ({})", goal, difficulty.name())
+            } else {
+                "Locked".to_string()
+            };
+            let mut detail_text = Text::new(detail);
+            detail_text.set_scale(PxScale::from(30.0 * self.scale));
+            canvas.draw(
+                &detail_text,
+                DrawParam::default()
+                    .dest(mint::Point2 {
+                        x: self.boundary_width * 0.42 + self.offset_x,
+                        y: y + 10.0 * self.scale,
+                    })
+                    .color(color),
+            );
+
+            self.draw_stars(
+                &mut mesh_builder,
+                self.boundary_width * 0.8 + self.offset_x,
+                y,
+                40.0 * self.scale,
+                self.campaign_progress.stars(&level.name),
+            )?;
+        }
+        let mesh = Mesh::from_data(ctx, mesh_builder.build());
+        canvas.draw(&mesh, DrawParam::default());
+
+        self.draw_centered_text(
+            ctx,
+            canvas,
+            "Up/Down to choose, Enter to play, ESC to go back",
+            30.0,
+            0.92,
+            Color::from_rgb(180, 180, 180),
+        )?;
+        Ok(())
+    }
+
+    // Stars earned and the way on, over the board the goal was met on
+    pub fn draw_level_complete(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.draw_dim_overlay(ctx, canvas)?;
+        let (Some(run), Some(index)) = (&self.last_run, self.campaign_index) else {
+            return Ok(());
+        };
+        let last_level = index + 1 >= self.campaign.len();
+
+        let title = if last_level {
+            "Campaign Complete!"
+        } else {
+            "Level Complete!"
+        };
+        self.draw_centered_text(ctx, canvas, title, 70.0, 0.08, Color::from_rgb(255, 215, 0))?;
+
+        let star_size = 90.0 * self.scale;
+        let mut mesh_builder = MeshBuilder::new();
+        self.draw_stars(
+            &mut mesh_builder,
+            (self.boundary_width - star_size * 3.2) / 2.0 + self.offset_x,
+            self.boundary_height * 0.2 + self.offset_y,
+            star_size,
+            run.stars,
+        )?;
+        let mesh = Mesh::from_data(ctx, mesh_builder.build());
+        canvas.draw(&mesh, DrawParam::default());
+
+        let minutes = (run.time_survived / 60.0).floor() as i32;
+        let seconds = run.time_survived % 60.0;
+        let mut lines = vec![
+            format!("Level {}: {}", index + 1, run.level_name),
+            format!("Time: {}:{:04.1}", minutes, seconds),
+            format!("Food Eaten: {}", run.food_eaten),
+            format!("Length: {}", run.length),
+        ];
+        if let Some(goal) = self.sim.goal {
+            lines.insert(1, goal.describe());
+        }
+        self.draw_centered_text(ctx, canvas, &lines.join("\n"), 38.0, 0.34, Color::WHITE)?;
+
+        if run.new_high_score {
+            self.draw_centered_text(
+                ctx,
+                canvas,
+                "New Best Rating!",
+                50.0,
+                0.6,
+                Color::from_rgb(0, 255, 0),
+            )?;
+        }
+
+        let mut options = LEVEL_COMPLETE_OPTIONS;
+        if last_level {
+            options[0] = "Finish";
+        }
+        self.draw_options(ctx, canvas, &options, self.level_complete_selection, 0.7)?;
+        Ok(())
+    }
+
+    // Three stars in a row from (x, y), the first `earned` of them filled in
+    fn draw_stars(
+        &self,
+        mesh_builder: &mut MeshBuilder,
+        x: f32,
+        y: f32,
+        size: f32,
+        earned: u8,
+    ) -> GameResult {
+        for star in 0..3 {
+            let center_x = x + size * 1.1 * star as f32 + size / 2.0;
+            let center_y = y + size / 2.0;
+            // Ten points alternating between the tips and the inner corners
+            let points: Vec<mint::Point2<f32>> = (0..10)
+                .map(|point| {
+                    let radius = if point % 2 == 0 { size / 2.0 } else { size / 5.0 };
+                    let angle = std::f32::consts::PI * (point as f32 / 5.0 - 0.5);
+                    mint::Point2 {
+                        x: center_x + radius * angle.cos(),
+                        y: center_y + radius * angle.sin(),
+                    }
+                })
+                .collect();
+            if star < earned {
+                mesh_builder.polygon(DrawMode::fill(), &points, Color::from_rgb(255, 215, 0))?;
+            } else {
+                mesh_builder.polygon(
+                    DrawMode::stroke(2.0 * self.scale),
+                    &points,
+                    Color::from_rgb(120, 120, 120),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    // Faint lines between the cells of the board on screen
+    fn add_grid_lines(&self, mesh_builder: &mut MeshBuilder) -> GameResult {
+        let (width, height) = self.board_size();
+        let board = self.board_rect();
+        let cell = self.scaled_snake_size;
+        let grid_color = Color::new(1.0, 1.0, 1.0, 0.08);
+        for x in 1..width {
+            let line_x = board.x + x as f32 * cell;
+            mesh_builder.line(
+                &[[line_x, board.y], [line_x, board.y + board.h]],
+                1.0,
+                grid_color,
+            )?;
+        }
+        for y in 1..height {
+            let line_y = board.y + y as f32 * cell;
+            mesh_builder.line(
+                &[[board.x, line_y], [board.x + board.w, line_y]],
+                1.0,
+                grid_color,
+            )?;
+        }
+        Ok(())
+    }
+
+    // Frames per second in the top left corner of the window
+    pub fn draw_fps(&self, ctx: &Context, canvas: &mut Canvas) {
+        let mut fps_text = Text::new(format!("FPS: {:.0}", ctx.time.fps()));
+        fps_text.set_scale(PxScale::from(24.0 * self.scale));
+        canvas.draw(
+            &fps_text,
+            DrawParam::default()
+                .dest(mint::Point2 { x: 8.0, y: 8.0 })
+                .color(Color::from_rgb(180, 180, 180)),
+        );
+    }
+
+    // The level being edited on the play grid, with the editor's state and keys around it
+    pub fn draw_editor(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        let mut mesh_builder = MeshBuilder::new();
+        let palette = self.theme.palette();
+        let level = &self.editor.level;
+        let board = self.board_rect();
+        let cell = self.scaled_snake_size;
+
+        // Faint grid so single cells are easy to hit
+        self.add_grid_lines(&mut mesh_builder)?;
+
+        for &wall in &level.walls {
+            mesh_builder.rectangle(DrawMode::fill(), self.scaled_rect(wall), palette.wall)?;
+        }
+        // Food spots are drawn smaller than food so they read as markers
+        for &spot in &level.food_spots {
+            let mut rect = self.scaled_rect(spot);
+            rect.translate([cell * 0.25, cell * 0.25]);
+            rect.scale(0.5, 0.5);
+            mesh_builder.rectangle(DrawMode::fill(), rect, palette.food)?;
+        }
+
+        // The start cell with an arrow pointing the way the snake sets off
+        let start = self.scaled_rect(level.start);
+        mesh_builder.rectangle(DrawMode::fill(), start, palette.snake)?;
+        let center = start.center();
+        let delta = level.direction.delta();
+        let (dx, dy) = (delta.x as f32 * cell * 0.3, delta.y as f32 * cell * 0.3);
+        mesh_builder.triangles(
+            &[
+                [center.x + dx, center.y + dy],
+                [center.x - dx - dy, center.y - dy + dx],
+                [center.x - dx + dy, center.y - dy - dx],
+            ],
+            palette.background,
+        )?;
+
+        mesh_builder.rectangle(DrawMode::stroke(5.0 * self.scale), board, palette.border)?;
+        let mesh = Mesh::from_data(ctx, mesh_builder.build());
+        canvas.draw(&mesh, DrawParam::default());
+
+        let goal = level.goal.map_or("None".to_string(), |goal| goal.describe());
+        let mut info_text = Text::new(format!(
+            "{}   {}x{}   Tool: {}   Start: {}   Goal: {}",
+            level.name,
+            level.width,
+            level.height,
+            self.editor.tool.name(),
+            level.direction.name(),
+            goal
+        ));
+        info_text.set_scale(PxScale::from(32.0 * self.scale));
+        canvas.draw(
+            &info_text,
+            DrawParam::default().dest(mint::Point2 {
+                x: self.boundary_width * 0.03 + self.offset_x,
+                y: self.boundary_height * 0.01 + self.offset_y,
+            }),
+        );
+
+        let (status, status_color) = match &self.editor.name_input {
+            Some(input) => (
+                format!("Name: {}_ (Enter to set, ESC to cancel)", input),
+                Color::from_rgb(255, 255, 0),
+            ),
+            None => (self.editor.status.clone(), Color::from_rgb(180, 180, 180)),
+        };
+        let mut status_text = Text::new(status);
+        status_text.set_scale(PxScale::from(28.0 * self.scale));
+        canvas.draw(
+            &status_text,
+            DrawParam::default()
+                .dest(mint::Point2 {
+                    x: self.boundary_width * 0.03 + self.offset_x,
+                    y: self.boundary_height * 0.05 + self.offset_y,
+                })
+                .color(status_color),
+        );
+
+        let mut hint_text = Text::new(
+            "Left click: paint   Right click: erase   1/2/3: Wall/Food/Start   Arrows: direction\n\
+             [ ]: width   - =: height   G: goal   , .: goal amount   ; ': goal time   C: clear\n\
+             F2: rename   S: save   O: open next saved   Enter: play-test   ESC: menu",
+        );
+        hint_text.set_scale(PxScale::from(26.0 * self.scale));
+        canvas.draw(
+            &hint_text,
+            DrawParam::default()
+                .dest(mint::Point2 {
+                    x: self.boundary_width * 0.03 + self.offset_x,
+                    y: self.boundary_height * 0.895 + self.offset_y,
+                })
+                .color(Color::from_rgb(180, 180, 180)),
+        );
+        Ok(())
+    }
+
+    pub fn draw_border(&self, mesh_builder: &mut MeshBuilder) {
+        let border_thickness = 5.0 * self.scale;
+        let color = self.theme.palette().border;
+        if self.sim.wall_rule == WallRule::Wrap {
+            self.draw_dashed_border(mesh_builder, border_thickness, color);
+            return;
+        }
+        let _ =
+            mesh_builder.rectangle(DrawMode::stroke(border_thickness), self.board_rect(), color);
+    }
+
+    // Wrap walls are drawn as one dash per cell so they read as passable
+    fn draw_dashed_border(&self, mesh_builder: &mut MeshBuilder, thickness: f32, color: Color) {
+        let cell = self.scaled_snake_size;
+        let dash = cell * 0.5;
+        let half = thickness / 2.0;
+        let board = self.board_rect();
+        let (left, top) = (board.x, board.y);
+        let (right, bottom) = (left + board.w, top + board.h);
+
+        for x in 0..self.sim.width {
+            let start = left + x as f32 * cell + (cell - dash) / 2.0;
+            for y in [top, bottom] {
+                let _ = mesh_builder.rectangle(
+                    DrawMode::fill(),
+                    Rect::new(start, y - half, dash, thickness),
+                    color,
+                );
+            }
+        }
+        for y in 0..self.sim.height {
+            let start = top + y as f32 * cell + (cell - dash) / 2.0;
+            for x in [left, right] {
+                let _ = mesh_builder.rectangle(
+                    DrawMode::fill(),
+                    Rect::new(x - half, start, thickness, dash),
+                    color,
+                );
+            }
+        }
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+// High score tables are split by the rules a run was played with and the board it was on,
+// the open board goes unnamed
+fn score_category(
+    difficulty: Difficulty,
+    wall_rule: WallRule,
+    play_mode: PlayMode,
+    level: Option<&str>,
+) -> String {
+    let mut rules = match wall_rule {
+        WallRule::Solid => difficulty.name().to_string(),
+        WallRule::Wrap => format!("{} Wrap", difficulty.name()),
+    };
+    match play_mode {
+        PlayMode::Coop => rules = format!("Co-op {}", rules),
+        PlayMode::Rivals => rules = format!("vs AI {}", rules),
+        PlayMode::Solo | PlayMode::Versus => {}
+    }
+    match level {
+        Some(name) => format!("{}: {}", name, rules),
+        None => rules,
+    }
+}
+
+// Game music pitch for a difficulty
+fn music_speed(difficulty: Difficulty) -> f32 {
+    match difficulty {
+        Difficulty::Easy => 0.8,
+        Difficulty::Normal => 1.0,
+        Difficulty::Hard => 1.2,
+        Difficulty::Special => 1.0,
+    }
+}
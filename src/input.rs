@@ -0,0 +1,590 @@
+use crate::constants::*;
+use crate::types::Direction;
+use ggez::event::Button;
+use ggez::input::gamepad::GamepadId;
+use ggez::input::keyboard::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+// Where an action is listened for. Two actions may share a key only when they are never
+// listened for at the same time.
+#[derive(Clone, Copy, PartialEq)]
+pub enum InputContext {
+    Game,   // A run being played
+    Menu,   // Every other screen but the editor
+    Editor, // The level editor, which takes Confirm and Back like the menus
+}
+
+impl InputContext {
+    const ALL: [InputContext; 3] = [InputContext::Game, InputContext::Menu, InputContext::Editor];
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    MoveUp, // Player one, alone these and the player two keys both steer
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Player2Up,
+    Player2Down,
+    Player2Left,
+    Player2Right,
+    Pause,
+    Mute,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    Confirm,
+    Back,
+    VolumeUp,
+    VolumeDown,
+    Easy,
+    Normal,
+    Hard,
+    Special,
+    DeleteReplay,
+    RestartReplay,
+    WallTool, // Editor actions from here on
+    FoodTool,
+    StartTool,
+    FaceUp,
+    FaceDown,
+    FaceLeft,
+    FaceRight,
+    Narrower,
+    Wider,
+    Shorter,
+    Taller,
+    NextGoal,
+    LessGoal,
+    MoreGoal,
+    LessGoalTime,
+    MoreGoalTime,
+    ClearLevel,
+    RenameLevel,
+    SaveLevel,
+    OpenLevel,
+}
+
+impl Action {
+    // In the order the controls screen lists them
+    pub const ALL: [Action; 44] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Player2Up,
+        Action::Player2Down,
+        Action::Player2Left,
+        Action::Player2Right,
+        Action::Pause,
+        Action::Mute,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuLeft,
+        Action::MenuRight,
+        Action::Confirm,
+        Action::Back,
+        Action::VolumeUp,
+        Action::VolumeDown,
+        Action::Easy,
+        Action::Normal,
+        Action::Hard,
+        Action::Special,
+        Action::DeleteReplay,
+        Action::RestartReplay,
+        Action::WallTool,
+        Action::FoodTool,
+        Action::StartTool,
+        Action::FaceUp,
+        Action::FaceDown,
+        Action::FaceLeft,
+        Action::FaceRight,
+        Action::Narrower,
+        Action::Wider,
+        Action::Shorter,
+        Action::Taller,
+        Action::NextGoal,
+        Action::LessGoal,
+        Action::MoreGoal,
+        Action::LessGoalTime,
+        Action::MoreGoalTime,
+        Action::ClearLevel,
+        Action::RenameLevel,
+        Action::SaveLevel,
+        Action::OpenLevel,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move Up",
+            Action::MoveDown => "Move Down",
+            Action::MoveLeft => "Move Left",
+            Action::MoveRight => "Move Right",
+            Action::Player2Up => "Player 2 Up",
+            Action::Player2Down => "Player 2 Down",
+            Action::Player2Left => "Player 2 Left",
+            Action::Player2Right => "Player 2 Right",
+            Action::Pause => "Pause",
+            Action::Mute => "Mute",
+            Action::MenuUp => "Menu Up",
+            Action::MenuDown => "Menu Down",
+            Action::MenuLeft => "Menu Left",
+            Action::MenuRight => "Menu Right",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+            Action::VolumeUp => "Volume Up",
+            Action::VolumeDown => "Volume Down",
+            Action::Easy => "Easy",
+            Action::Normal => "Normal",
+            Action::Hard => "Hard",
+            Action::Special => "Special",
+            Action::DeleteReplay => "Delete Replay",
+            Action::RestartReplay => "Restart Replay",
+            Action::WallTool => "Wall Tool",
+            Action::FoodTool => "Food Tool",
+            Action::StartTool => "Start Tool",
+            Action::FaceUp => "Face Up",
+            Action::FaceDown => "Face Down",
+            Action::FaceLeft => "Face Left",
+            Action::FaceRight => "Face Right",
+            Action::Narrower => "Narrower",
+            Action::Wider => "Wider",
+            Action::Shorter => "Shorter",
+            Action::Taller => "Taller",
+            Action::NextGoal => "Next Goal",
+            Action::LessGoal => "Goal Down",
+            Action::MoreGoal => "Goal Up",
+            Action::LessGoalTime => "Time Down",
+            Action::MoreGoalTime => "Time Up",
+            Action::ClearLevel => "Clear Level",
+            Action::RenameLevel => "Rename Level",
+            Action::SaveLevel => "Save Level",
+            Action::OpenLevel => "Open Level",
+        }
+    }
+
+    // None for actions that work everywhere
+    pub fn context(self) -> Option<InputContext> {
+        match self {
+            Action::MoveUp
+            | Action::MoveDown
+            | Action::MoveLeft
+            | Action::MoveRight
+            | Action::Player2Up
+            | Action::Player2Down
+            | Action::Player2Left
+            | Action::Player2Right
+            | Action::Pause => Some(InputContext::Game),
+            Action::Mute => None,
+            Action::WallTool
+            | Action::FoodTool
+            | Action::StartTool
+            | Action::FaceUp
+            | Action::FaceDown
+            | Action::FaceLeft
+            | Action::FaceRight
+            | Action::Narrower
+            | Action::Wider
+            | Action::Shorter
+            | Action::Taller
+            | Action::NextGoal
+            | Action::LessGoal
+            | Action::MoreGoal
+            | Action::LessGoalTime
+            | Action::MoreGoalTime
+            | Action::ClearLevel
+            | Action::RenameLevel
+            | Action::SaveLevel
+            | Action::OpenLevel => Some(InputContext::Editor),
+            _ => Some(InputContext::Menu),
+        }
+    }
+
+    fn listens_in(self, context: InputContext) -> bool {
+        match self.context() {
+            None => true,
+            // The editor plays its level on Confirm and leaves on Back
+            Some(InputContext::Menu) if matches!(self, Action::Confirm | Action::Back) => {
+                context != InputContext::Game
+            }
+            Some(own) => own == context,
+        }
+    }
+
+    fn overlaps(self, other: Action) -> bool {
+        InputContext::ALL
+            .iter()
+            .any(|&context| self.listens_in(context) && other.listens_in(context))
+    }
+
+    fn default_keys(self) -> Vec<KeyCode> {
+        match self {
+            Action::MoveUp => vec![KeyCode::W],
+            Action::MoveDown => vec![KeyCode::S],
+            Action::MoveLeft => vec![KeyCode::A],
+            Action::MoveRight => vec![KeyCode::D],
+            Action::Player2Up => vec![KeyCode::Up],
+            Action::Player2Down => vec![KeyCode::Down],
+            Action::Player2Left => vec![KeyCode::Left],
+            Action::Player2Right => vec![KeyCode::Right],
+            Action::Pause => vec![KeyCode::Escape, KeyCode::P, KeyCode::Space],
+            Action::Mute => vec![KeyCode::M],
+            Action::MenuUp => vec![KeyCode::Up, KeyCode::W],
+            Action::MenuDown => vec![KeyCode::Down, KeyCode::S],
+            Action::MenuLeft => vec![KeyCode::Left, KeyCode::A],
+            Action::MenuRight => vec![KeyCode::Right, KeyCode::D],
+            Action::Confirm => vec![KeyCode::Return, KeyCode::Space],
+            Action::Back => vec![KeyCode::Escape],
+            Action::VolumeUp => vec![KeyCode::Equals, KeyCode::Plus],
+            Action::VolumeDown => vec![KeyCode::Minus],
+            Action::Easy => vec![KeyCode::Key1],
+            Action::Normal => vec![KeyCode::Key2],
+            Action::Hard => vec![KeyCode::Key3],
+            Action::Special => vec![KeyCode::Key4],
+            Action::DeleteReplay => vec![KeyCode::Delete],
+            Action::RestartReplay => vec![KeyCode::R],
+            Action::WallTool => vec![KeyCode::Key1],
+            Action::FoodTool => vec![KeyCode::Key2],
+            Action::StartTool => vec![KeyCode::Key3],
+            Action::FaceUp => vec![KeyCode::Up],
+            Action::FaceDown => vec![KeyCode::Down],
+            Action::FaceLeft => vec![KeyCode::Left],
+            Action::FaceRight => vec![KeyCode::Right],
+            Action::Narrower => vec![KeyCode::LBracket],
+            Action::Wider => vec![KeyCode::RBracket],
+            Action::Shorter => vec![KeyCode::Minus],
+            Action::Taller => vec![KeyCode::Equals, KeyCode::Plus],
+            Action::NextGoal => vec![KeyCode::G],
+            Action::LessGoal => vec![KeyCode::Comma],
+            Action::MoreGoal => vec![KeyCode::Period],
+            Action::LessGoalTime => vec![KeyCode::Semicolon],
+            Action::MoreGoalTime => vec![KeyCode::Apostrophe],
+            Action::ClearLevel => vec![KeyCode::C],
+            // A key without a character, so it doesn't end up in the name
+            Action::RenameLevel => vec![KeyCode::F2],
+            Action::SaveLevel => vec![KeyCode::S],
+            Action::OpenLevel => vec![KeyCode::O],
+        }
+    }
+}
+
+// The keys bound to each action, stored in the settings file by action name
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(transparent)]
+pub struct KeyBindings(BTreeMap<Action, Vec<KeyCode>>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings(
+            Action::ALL
+                .iter()
+                .map(|&action| (action, action.default_keys()))
+                .collect(),
+        )
+    }
+}
+
+impl KeyBindings {
+    // Actions added since the settings were saved get their default keys
+    pub fn with_defaults(mut self) -> KeyBindings {
+        for action in Action::ALL {
+            self.0
+                .entry(action)
+                .or_insert_with(|| action.default_keys());
+        }
+        self
+    }
+
+    // The action a key triggers on screens of the given context
+    pub fn action(&self, key: KeyCode, context: InputContext) -> Option<Action> {
+        self.0
+            .iter()
+            .find(|(action, keys)| action.listens_in(context) && keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn is_bound(&self, action: Action, key: Option<KeyCode>) -> bool {
+        key.is_some_and(|key| self.keys(action).contains(&key))
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.0.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+
+    // The action that already uses `key` while `action` would be listening, if any
+    pub fn conflict(&self, action: Action, key: KeyCode) -> Option<Action> {
+        self.0
+            .iter()
+            .find(|(other, keys)| {
+                **other != action && other.overlaps(action) && keys.contains(&key)
+            })
+            .map(|(other, _)| *other)
+    }
+
+    // Replaces whatever the action was bound to with a single key
+    pub fn bind(&mut self, action: Action, key: KeyCode) {
+        self.0.insert(action, vec![key]);
+    }
+
+    // The keys of an action as the controls screen shows them
+    pub fn describe(&self, action: Action) -> String {
+        let names: Vec<String> = self.keys(action).iter().map(|&key| key_name(key)).collect();
+        if names.is_empty() {
+            "Unbound".to_string()
+        } else {
+            names.join(", ")
+        }
+    }
+}
+
+// A readable name for a key, the debug name unless that is unclear
+pub fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Return => "Enter".to_string(),
+        KeyCode::Back => "Backspace".to_string(),
+        KeyCode::Equals => "=".to_string(),
+        KeyCode::Minus => "-".to_string(),
+        KeyCode::Plus => "+".to_string(),
+        _ => {
+            let name = format!("{:?}", key);
+            match name.strip_prefix("Key") {
+                Some(digit) => digit.to_string(),
+                None => name,
+            }
+        }
+    }
+}
+
+// Turns a player pressed ahead of the snake, taken one per tick. Each is checked against
+// the direction the snake will have by then, so repeats and reversals are dropped instead
+// of using up a tick, and a full queue ignores further presses.
+#[derive(Default)]
+pub struct TurnQueue(VecDeque<Direction>);
+
+impl TurnQueue {
+    // `heading` is where the snake points now, `length` how many turns may wait
+    pub fn push(&mut self, direction: Direction, heading: Direction, length: usize) {
+        let heading = self.0.back().copied().unwrap_or(heading);
+        let turns = direction != heading && direction != heading.opposite();
+        if turns && self.0.len() < length {
+            self.0.push_back(direction);
+        }
+    }
+
+    // The turn for this tick, if one is waiting
+    pub fn pop(&mut self) -> Option<Direction> {
+        self.0.pop_front()
+    }
+}
+
+// A connected gamepad and the player it steers
+pub struct GamepadSlot {
+    pub id: GamepadId,
+    pub name: String,
+    pub player: usize,
+    stick: [f32; 2],                    // Left stick x and y, up is positive
+    stick_direction: Option<Direction>, // Where the stick points once quantized
+}
+
+impl GamepadSlot {
+    pub fn new(id: GamepadId, name: String, player: usize) -> GamepadSlot {
+        GamepadSlot {
+            id,
+            name,
+            player,
+            stick: [0.0, 0.0],
+            stick_direction: None,
+        }
+    }
+
+    // Takes one stick axis and returns the direction the stick was just pushed to, if
+    // any. Like a d-pad press it fires once, the stick has to leave that direction before
+    // it fires again.
+    pub fn move_stick(&mut self, axis: usize, value: f32) -> Option<Direction> {
+        self.stick[axis] = value;
+        let [x, y] = self.stick;
+        let direction = if x.abs().max(y.abs()) < STICK_DEADZONE {
+            None
+        } else if x.abs() > y.abs() {
+            Some(if x > 0.0 {
+                Direction::Right
+            } else {
+                Direction::Left
+            })
+        } else {
+            Some(if y > 0.0 {
+                Direction::Up
+            } else {
+                Direction::Down
+            })
+        };
+        if direction == self.stick_direction {
+            return None;
+        }
+        self.stick_direction = direction;
+        direction
+    }
+}
+
+// The d-pad button pointing the same way, so the stick can stand in for it
+pub fn dpad_button(direction: Direction) -> Button {
+    match direction {
+        Direction::Up => Button::DPadUp,
+        Direction::Down => Button::DPadDown,
+        Direction::Left => Button::DPadLeft,
+        Direction::Right => Button::DPadRight,
+    }
+}
+
+// What a gamepad button means on screens of the given context. Pads have a fixed layout:
+// the d-pad steers or moves through menus, Start pauses, A confirms and B goes back. On
+// the replay screens X deletes and Y restarts, in the editor the d-pad turns the start.
+pub fn button_action(button: Button, context: InputContext, player: usize) -> Option<Action> {
+    let second = player > 0;
+    match (context, button) {
+        (InputContext::Game, Button::DPadUp) if second => Some(Action::Player2Up),
+        (InputContext::Game, Button::DPadDown) if second => Some(Action::Player2Down),
+        (InputContext::Game, Button::DPadLeft) if second => Some(Action::Player2Left),
+        (InputContext::Game, Button::DPadRight) if second => Some(Action::Player2Right),
+        (InputContext::Game, Button::DPadUp) => Some(Action::MoveUp),
+        (InputContext::Game, Button::DPadDown) => Some(Action::MoveDown),
+        (InputContext::Game, Button::DPadLeft) => Some(Action::MoveLeft),
+        (InputContext::Game, Button::DPadRight) => Some(Action::MoveRight),
+        (InputContext::Game, Button::Start) => Some(Action::Pause),
+        (InputContext::Menu, Button::DPadUp) => Some(Action::MenuUp),
+        (InputContext::Menu, Button::DPadDown) => Some(Action::MenuDown),
+        (InputContext::Menu, Button::DPadLeft) => Some(Action::MenuLeft),
+        (InputContext::Menu, Button::DPadRight) => Some(Action::MenuRight),
+        (InputContext::Menu, Button::South | Button::Start) => Some(Action::Confirm),
+        (InputContext::Menu, Button::East) => Some(Action::Back),
+        (InputContext::Menu, Button::West) => Some(Action::DeleteReplay),
+        (InputContext::Menu, Button::North) => Some(Action::RestartReplay),
+        (InputContext::Editor, Button::DPadUp) => Some(Action::FaceUp),
+        (InputContext::Editor, Button::DPadDown) => Some(Action::FaceDown),
+        (InputContext::Editor, Button::DPadLeft) => Some(Action::FaceLeft),
+        (InputContext::Editor, Button::DPadRight) => Some(Action::FaceRight),
+        (InputContext::Editor, Button::South | Button::Start) => Some(Action::Confirm),
+        (InputContext::Editor, Button::East) => Some(Action::Back),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_defaults_have_no_conflicts() {
+        let bindings = KeyBindings::default();
+        for action in Action::ALL {
+            for &key in bindings.keys(action) {
+                assert!(
+                    bindings.conflict(action, key).is_none(),
+                    "{} shares {} with another action",
+                    action.name(),
+                    key_name(key)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn a_key_taken_in_the_same_context_is_a_conflict() {
+        let bindings = KeyBindings::default();
+
+        assert!(bindings.conflict(Action::MenuUp, KeyCode::Return) == Some(Action::Confirm));
+        // Confirm and Back are heard in the editor too, Mute everywhere
+        assert!(bindings.conflict(Action::SaveLevel, KeyCode::Escape) == Some(Action::Back));
+        assert!(bindings.conflict(Action::Pause, KeyCode::M) == Some(Action::Mute));
+    }
+
+    #[test]
+    fn the_same_key_can_serve_different_contexts() {
+        let mut bindings = KeyBindings::default();
+
+        assert!(bindings.conflict(Action::Pause, KeyCode::Return).is_none());
+        bindings.bind(Action::Pause, KeyCode::Return);
+
+        assert!(bindings.action(KeyCode::Return, InputContext::Game) == Some(Action::Pause));
+        assert!(bindings.action(KeyCode::Return, InputContext::Menu) == Some(Action::Confirm));
+    }
+
+    #[test]
+    fn binding_replaces_the_old_keys() {
+        let mut bindings = KeyBindings::default();
+
+        bindings.bind(Action::Confirm, KeyCode::F);
+
+        assert!(bindings.keys(Action::Confirm) == [KeyCode::F]);
+        assert!(bindings.is_bound(Action::Confirm, Some(KeyCode::F)));
+        assert!(!bindings.is_bound(Action::Confirm, Some(KeyCode::Return)));
+        assert!(!bindings.is_bound(Action::Confirm, None));
+    }
+
+    #[test]
+    fn defaults_fill_in_only_the_missing_actions() {
+        let saved = KeyBindings(BTreeMap::from([(Action::Confirm, vec![KeyCode::F])]));
+
+        let bindings = saved.with_defaults();
+
+        assert!(bindings.keys(Action::Confirm) == [KeyCode::F]);
+        assert!(bindings.keys(Action::Back) == [KeyCode::Escape]);
+        assert!(Action::ALL
+            .iter()
+            .all(|&action| !bindings.keys(action).is_empty()));
+    }
+
+    #[test]
+    fn the_turn_queue_drops_repeats_and_reversals_of_the_last_turn() {
+        let mut queue = TurnQueue::default();
+
+        queue.push(Direction::Right, Direction::Right, INPUT_QUEUE_LENGTH);
+        queue.push(Direction::Left, Direction::Right, INPUT_QUEUE_LENGTH);
+        queue.push(Direction::Up, Direction::Right, INPUT_QUEUE_LENGTH);
+        queue.push(Direction::Up, Direction::Right, INPUT_QUEUE_LENGTH);
+        queue.push(Direction::Down, Direction::Right, INPUT_QUEUE_LENGTH);
+        queue.push(Direction::Left, Direction::Right, INPUT_QUEUE_LENGTH);
+
+        assert_eq!(queue.pop(), Some(Direction::Up));
+        assert_eq!(queue.pop(), Some(Direction::Left));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn the_turn_queue_holds_at_most_its_length() {
+        let mut queue = TurnQueue::default();
+        let turns = [
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+        ];
+
+        for turn in turns {
+            queue.push(turn, Direction::Right, 3);
+        }
+
+        assert_eq!(queue.pop(), Some(Direction::Up));
+        assert_eq!(queue.pop(), Some(Direction::Left));
+        assert_eq!(queue.pop(), Some(Direction::Down));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn the_turn_queue_hands_out_one_turn_per_tick() {
+        let mut queue = TurnQueue::default();
+        queue.push(Direction::Up, Direction::Right, 3);
+        queue.push(Direction::Left, Direction::Right, 3);
+
+        assert_eq!(queue.pop(), Some(Direction::Up));
+        // Room frees up as turns are taken
+        queue.push(Direction::Down, Direction::Right, 3);
+        queue.push(Direction::Right, Direction::Right, 3);
+        assert_eq!(queue.pop(), Some(Direction::Left));
+        assert_eq!(queue.pop(), Some(Direction::Down));
+        assert_eq!(queue.pop(), Some(Direction::Right));
+        assert_eq!(queue.pop(), None);
+    }
+}
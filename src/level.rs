@@ -0,0 +1,460 @@
+use crate::constants::*;
+use crate::types::*;
+use ggez::Context;
+use nalgebra as na;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+// A board layout. Levels are plain text files, a header followed by the grid:
+//
+//   name: Pillars
+//   size: 28x21
+//   target: 30
+//   direction: Right
+//   ---
+//   ############################
+//   #..S.......................#
+//
+// `#` is a wall, `S` the snake's start, `F` a fixed food spot and `.` or a
+// space an empty cell. Every header field but name and size is optional:
+//
+//   target: 30            End the run as a win at this score
+//   direction: Right      Which way the snake starts moving
+//   difficulty: Hard      Speed the level is always played at in the campaign
+//   goal: length 12       Or `food 8 in 40` or `survive 30`, see Goal
+//   stars: 30 20          Thresholds for two and three stars, see Level::stars
+#[derive(Clone)]
+pub struct Level {
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    pub walls: Vec<na::Point2<i32>>,
+    pub start: na::Point2<i32>,
+    pub direction: Direction,
+    pub food_spots: Vec<na::Point2<i32>>, // Food only appears here when any are given
+    pub target_score: Option<i32>,
+    pub difficulty: Option<Difficulty>,
+    pub goal: Option<Goal>,
+    pub star_thresholds: Option<(f32, f32)>,
+}
+
+// What a campaign level asks of the player
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Goal {
+    Length(usize),                          // Grow the snake to this many segments
+    FoodWithin { food: u32, seconds: f32 }, // Eat this much before the clock runs out
+    Survive(f32),                           // Stay alive this many seconds
+}
+
+impl Goal {
+    pub fn describe(self) -> String {
+        match self {
+            Goal::Length(length) => format!("Reach length {}", length),
+            Goal::FoodWithin { food, seconds } => format!("Eat {} food in {:.0}s", food, seconds),
+            Goal::Survive(seconds) => format!("Survive {:.0}s", seconds),
+        }
+    }
+
+    // The header form `parse` reads back
+    fn header(self) -> String {
+        match self {
+            Goal::Length(length) => format!("length {}", length),
+            Goal::FoodWithin { food, seconds } => format!("food {} in {}", food, seconds),
+            Goal::Survive(seconds) => format!("survive {}", seconds),
+        }
+    }
+
+    fn parse(value: &str) -> Option<Goal> {
+        let words: Vec<&str> = value.split_whitespace().collect();
+        match words.as_slice() {
+            ["length", length] => Some(Goal::Length(length.parse().ok()?)),
+            ["food", food, "in", seconds] => Some(Goal::FoodWithin {
+                food: food.parse().ok()?,
+                seconds: seconds.parse().ok()?,
+            }),
+            ["survive", seconds] => Some(Goal::Survive(seconds.parse().ok()?)),
+            _ => None,
+        }
+    }
+}
+
+impl Level {
+    // The classic empty board
+    pub fn open() -> Level {
+        Level {
+            name: OPEN_BOARD_NAME.to_string(),
+            width: GRID_WIDTH,
+            height: GRID_HEIGHT,
+            walls: Vec::new(),
+            start: na::Point2::new(GRID_WIDTH / 4, GRID_HEIGHT / 4),
+            direction: Direction::Right,
+            food_spots: Vec::new(),
+            target_score: None,
+            difficulty: None,
+            goal: None,
+            star_thresholds: None,
+        }
+    }
+
+    // One to three stars for a completed goal. Survive goals are rated by the food
+    // eaten along the way, the others by how many seconds they took; the thresholds
+    // are the two and three star marks. Without thresholds completing is enough.
+    pub fn stars(&self, elapsed: f32, food_eaten: u32) -> u8 {
+        let Some((two, three)) = self.star_thresholds else {
+            return 3;
+        };
+        let earned = |threshold: f32| match self.goal {
+            Some(Goal::Survive(_)) => food_eaten as f32 >= threshold,
+            _ => elapsed <= threshold,
+        };
+        if earned(three) {
+            3
+        } else if earned(two) {
+            2
+        } else {
+            1
+        }
+    }
+
+    // Writes the level in the same format `parse` reads
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "name: {}\nsize: {}x{}\n",
+            self.name, self.width, self.height
+        );
+        if let Some(target) = self.target_score {
+            text.push_str(&format!("target: {}\n", target));
+        }
+        text.push_str(&format!("direction: {}\n", self.direction.name()));
+        if let Some(difficulty) = self.difficulty {
+            text.push_str(&format!("difficulty: {}\n", difficulty.name()));
+        }
+        if let Some(goal) = self.goal {
+            text.push_str(&format!("goal: {}\n", goal.header()));
+        }
+        if let Some((two, three)) = self.star_thresholds {
+            text.push_str(&format!("stars: {} {}\n", two, three));
+        }
+        text.push_str("---\n");
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pos = na::Point2::new(x, y);
+                text.push(if pos == self.start {
+                    'S'
+                } else if self.walls.contains(&pos) {
+                    '#'
+                } else if self.food_spots.contains(&pos) {
+                    'F'
+                } else {
+                    '.'
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Level, String> {
+        let mut name = None;
+        let mut size = None;
+        let mut target_score = None;
+        let mut direction = Direction::Right;
+        let mut difficulty = None;
+        let mut goal = None;
+        let mut star_thresholds = None;
+
+        let mut lines = text.lines();
+        for line in lines.by_ref() {
+            let line = line.trim();
+            if line == "---" {
+                break;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| format!("Expected `key: value` in the header, got {:?}", line))?;
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "name" => name = Some(value.to_string()),
+                "size" => {
+                    let (width, height) = value
+                        .split_once('x')
+                        .ok_or_else(|| format!("Size must look like 28x21, got {:?}", value))?;
+                    let parse = |side: &str| {
+                        side.trim()
+                            .parse::<i32>()
+                            .map_err(|_| format!("Invalid board size {:?}", value))
+                    };
+                    size = Some((parse(width)?, parse(height)?));
+                }
+                "target" => {
+                    target_score = Some(
+                        value
+                            .parse::<i32>()
+                            .map_err(|_| format!("Invalid target score {:?}", value))?,
+                    );
+                }
+                "direction" => {
+                    direction = parse_direction(value)
+                        .ok_or_else(|| format!("Invalid start direction {:?}", value))?;
+                }
+                "difficulty" => {
+                    difficulty = Some(
+                        Difficulty::ALL
+                            .into_iter()
+                            .find(|difficulty| difficulty.name().eq_ignore_ascii_case(value))
+                            .ok_or_else(|| format!("Invalid difficulty {:?}", value))?,
+                    );
+                }
+                "goal" => {
+                    goal = Some(
+                        Goal::parse(&value.to_ascii_lowercase())
+                            .ok_or_else(|| format!("Invalid goal {:?}", value))?,
+                    );
+                }
+                "stars" => {
+                    let thresholds: Vec<f32> = value
+                        .split_whitespace()
+                        .map(|threshold| threshold.parse::<f32>())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| format!("Invalid star thresholds {:?}", value))?;
+                    match thresholds.as_slice() {
+                        [two, three] => star_thresholds = Some((*two, *three)),
+                        _ => return Err(format!("Expected two star thresholds, got {:?}", value)),
+                    }
+                }
+                other => return Err(format!("Unknown header field {:?}", other)),
+            }
+        }
+
+        let name = name.ok_or("The header is missing a name")?;
+        let (width, height) = size.ok_or("The header is missing a size")?;
+        if !(MIN_LEVEL_SIZE..=MAX_LEVEL_SIZE).contains(&width)
+            || !(MIN_LEVEL_SIZE..=MAX_LEVEL_SIZE).contains(&height)
+        {
+            return Err(format!("Board size {}x{} is out of range", width, height));
+        }
+
+        // Rows are taken by position, a row of empty cells may be spaces or nothing at all.
+        // Only blank lines after the last row are dropped.
+        let mut rows: Vec<&str> = lines.collect();
+        while rows.len() > height as usize && rows.last().is_some_and(|row| row.trim().is_empty()) {
+            rows.pop();
+        }
+        if rows.len() != height as usize {
+            return Err(format!(
+                "Expected {} grid rows, found {}",
+                height,
+                rows.len()
+            ));
+        }
+
+        let mut walls = Vec::new();
+        let mut food_spots = Vec::new();
+        let mut start = None;
+        for (y, row) in rows.iter().enumerate() {
+            let cells: Vec<char> = row.trim_end().chars().collect();
+            if cells.len() > width as usize {
+                return Err(format!("Row {} is wider than {} cells", y + 1, width));
+            }
+            for (x, cell) in cells.into_iter().enumerate() {
+                let pos = na::Point2::new(x as i32, y as i32);
+                match cell {
+                    '#' => walls.push(pos),
+                    'F' => food_spots.push(pos),
+                    'S' if start.is_some() => return Err("More than one start cell".to_string()),
+                    'S' => start = Some(pos),
+                    '.' | ' ' => {}
+                    other => return Err(format!("Unknown cell {:?} in row {}", other, y + 1)),
+                }
+            }
+        }
+
+        Ok(Level {
+            name,
+            width,
+            height,
+            walls,
+            start: start.ok_or("The grid has no start cell `S`")?,
+            direction,
+            food_spots,
+            target_score,
+            difficulty,
+            goal,
+            star_thresholds,
+        })
+    }
+}
+
+// Every level file in a resource directory, sorted by file name.
+// A file that fails to parse is reported and skipped.
+pub fn load_levels(ctx: &Context, directory: &str) -> Vec<Level> {
+    let mut levels = Vec::new();
+    let mut paths: Vec<PathBuf> = match ctx.fs.read_dir(directory) {
+        Ok(paths) => paths
+            .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to list levels in {}: {}", directory, e);
+            return levels;
+        }
+    };
+    paths.sort();
+
+    for path in paths {
+        let mut text = String::new();
+        if let Err(e) = ctx
+            .fs
+            .open(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|mut file| file.read_to_string(&mut text).map_err(|e| e.to_string()))
+        {
+            eprintln!("Failed to read level {:?}: {}", path, e);
+            continue;
+        }
+        match Level::parse(&text) {
+            Ok(level) => levels.push(level),
+            Err(e) => eprintln!("Skipping level {:?}: {}", path, e),
+        }
+    }
+    levels
+}
+
+// Same as load_levels, for levels saved outside the resources, e.g. by the editor
+pub fn load_level_files(directory: &Path) -> Vec<Level> {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Vec::new(); // Nothing saved yet
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+        .collect();
+    paths.sort();
+
+    let mut levels = Vec::new();
+    for path in paths {
+        match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Level::parse(&text))
+        {
+            Ok(level) => levels.push(level),
+            Err(e) => eprintln!("Skipping level {:?}: {}", path, e),
+        }
+    }
+    levels
+}
+
+fn parse_direction(value: &str) -> Option<Direction> {
+    match value.to_ascii_lowercase().as_str() {
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL: &str = "name: Gaps
+size: 6x5
+target: 12
+direction: Down
+goal: food 3 in 20
+stars: 10 5
+---
+######
+#S  F#
+
+#.  .#
+######
+";
+
+    #[test]
+    fn parses_a_level_with_empty_rows() {
+        let level = Level::parse(LEVEL).unwrap();
+
+        assert_eq!(level.name, "Gaps");
+        assert_eq!((level.width, level.height), (6, 5));
+        assert_eq!(level.start, na::Point2::new(1, 1));
+        assert_eq!(level.direction, Direction::Down);
+        assert_eq!(level.food_spots, vec![na::Point2::new(4, 1)]);
+        assert_eq!(level.target_score, Some(12));
+        assert_eq!(
+            level.goal,
+            Some(Goal::FoodWithin {
+                food: 3,
+                seconds: 20.0
+            })
+        );
+        assert_eq!(level.star_thresholds, Some((10.0, 5.0)));
+        // The empty third row has no walls, the rows around it keep theirs
+        assert!(level.walls.iter().all(|wall| wall.y != 2));
+        assert_eq!(level.walls.len(), 6 + 2 + 2 + 6);
+    }
+
+    #[test]
+    fn parses_a_row_of_spaces() {
+        let text = LEVEL.replace("\n\n", "\n      \n");
+        let level = Level::parse(&text).unwrap();
+        assert!(level.walls.iter().all(|wall| wall.y != 2));
+    }
+
+    #[test]
+    fn rejects_a_missing_row() {
+        let text = LEVEL.strip_suffix("######\n").unwrap();
+        assert!(Level::parse(text).is_err());
+    }
+
+    #[test]
+    fn to_text_reads_back_as_the_same_level() {
+        let level = Level::parse(LEVEL).unwrap();
+
+        let parsed = Level::parse(&level.to_text()).unwrap();
+
+        assert_eq!(parsed.to_text(), level.to_text());
+        assert_eq!(parsed.walls, level.walls);
+        assert_eq!(parsed.food_spots, level.food_spots);
+        assert_eq!(parsed.start, level.start);
+        assert_eq!(parsed.goal, level.goal);
+    }
+
+    #[test]
+    fn every_bundled_level_parses() {
+        for directory in ["resources/levels", "resources/campaign"] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(directory);
+            let count = fs::read_dir(&path).unwrap().count();
+            assert_eq!(
+                load_level_files(&path).len(),
+                count,
+                "a level in {}",
+                directory
+            );
+        }
+    }
+
+    #[test]
+    fn stars_rate_timed_goals_by_seconds_and_survive_goals_by_food() {
+        // Food 3 in 20, three stars in 5s or less and two in 10s or less
+        let timed = Level::parse(LEVEL).unwrap();
+        assert_eq!(timed.stars(5.0, 3), 3);
+        assert_eq!(timed.stars(8.0, 3), 2);
+        assert_eq!(timed.stars(15.0, 3), 1);
+
+        let mut survive = timed.clone();
+        survive.goal = Some(Goal::Survive(30.0));
+        survive.star_thresholds = Some((4.0, 8.0));
+        assert_eq!(survive.stars(30.0, 8), 3);
+        assert_eq!(survive.stars(30.0, 5), 2);
+        assert_eq!(survive.stars(30.0, 1), 1);
+
+        survive.star_thresholds = None;
+        assert_eq!(survive.stars(30.0, 0), 3);
+    }
+}
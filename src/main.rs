@@ -1,26 +1,41 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+mod ai;
+mod audio;
+mod campaign;
 mod constants;
+mod editor;
 mod types;
 mod game_state;
+mod input;
+mod level;
+mod menu;
+mod replay;
+mod scores;
+mod settings;
+mod simulation;
+mod storage;
+mod theme;
 mod event_handler;
 
 use ggez::{event, GameResult};
 use std::path::PathBuf;
+use crate::constants::{GAME_AUTHOR, GAME_ID};
 use crate::game_state::GameState;
 
 fn main() -> GameResult {
     // Determine if running as a bundle (CARGO_MANIFEST_DIR not set)
     let is_bundle = std::env::var("CARGO_MANIFEST_DIR").is_err();
 
-    let (mut ctx, event_loop) = ggez::ContextBuilder::new("Rust Snake Game", "Jacob Mish")
-        .window_setup(ggez::conf::WindowSetup::default().title("Snake Game"))
-        .window_mode(
-            ggez::conf::WindowMode::default()
-                .dimensions(constants::REFERENCE_WIDTH, constants::REFERENCE_HEIGHT)
-                .min_dimensions(constants::MIN_WINDOW_WIDTH, constants::MIN_WINDOW_HEIGHT)
-                .resizable(true)
-                .transparent(true),
-        )
+    // Vsync can't be changed once the window exists
+    let vsync = settings::startup_vsync();
+
+    let (mut ctx, event_loop) = ggez::ContextBuilder::new(GAME_ID, GAME_AUTHOR)
+        .window_setup(ggez::conf::WindowSetup::default().title("Snake Game").vsync(vsync))
+        .window_mode(GameState::window_mode(
+            constants::REFERENCE_WIDTH,
+            constants::REFERENCE_HEIGHT,
+            false,
+        ))
         .add_resource_path(if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
             let mut path = PathBuf::from(manifest_dir);
             path.push("resources");
@@ -43,6 +58,26 @@ fn main() -> GameResult {
         .build()?;
 
     // Pass the is_bundle flag to GameState::new
-    let state = GameState::new(&mut ctx, is_bundle)?;
+    let mut state = GameState::new(&mut ctx, is_bundle)?;
+    state.fixed_seed = seed_from_args();
     event::run(ctx, event_loop, state)
 }
+
+// Reads `--seed <N>` or `--seed=<N>` so a run can be reproduced from the command line
+fn seed_from_args() -> Option<u64> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = if arg == "--seed" {
+            args.next()
+        } else if let Some(value) = arg.strip_prefix("--seed=") {
+            Some(value.to_string())
+        } else {
+            continue;
+        };
+        match value.as_deref().map(str::parse::<u64>) {
+            Some(Ok(seed)) => return Some(seed),
+            _ => eprintln!("Ignoring invalid --seed value: {:?}", value),
+        }
+    }
+    None
+}
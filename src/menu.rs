@@ -0,0 +1,185 @@
+use crate::constants::*;
+use ggez::graphics::{PxScale, Rect, Text};
+use ggez::{mint, Context, GameResult};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MenuPage {
+    Main,
+    Play,
+    Multiplayer,
+    Settings,
+}
+
+impl MenuPage {
+    pub fn title(self) -> &'static str {
+        match self {
+            MenuPage::Main => "Snake",
+            MenuPage::Play => "Play",
+            MenuPage::Multiplayer => "Multiplayer",
+            MenuPage::Settings => "Settings",
+        }
+    }
+
+    // The page Back returns to, None on the main page
+    pub fn parent(self) -> Option<MenuPage> {
+        match self {
+            MenuPage::Main => None,
+            MenuPage::Play | MenuPage::Settings => Some(MenuPage::Main),
+            MenuPage::Multiplayer => Some(MenuPage::Play),
+        }
+    }
+}
+
+// What a menu row does, see GameState::activate_menu_item and adjust_menu_item
+#[derive(Clone, Copy, PartialEq)]
+pub enum MenuEntry {
+    Open(MenuPage),
+    Back,
+    Quit,
+    Start,
+    Difficulty,
+    Level,
+    Walls,
+    Seed,
+    Ghost,
+    Campaign,
+    Editor,
+    Versus,
+    BestOf,
+    Coop,
+    CoopRespawn,
+    Rivals,
+    RivalCount,
+    RivalSkill,
+    HighScores,
+    Replays,
+    Volume,
+    Music,
+    Effects,
+    Mute,
+    Fullscreen,
+    Vsync,
+    Theme,
+    GridLines,
+    ShowFps,
+    InputQueue,
+    Controls,
+}
+
+// A row of a menu. Rows with a value are options, Left/Right change them.
+pub struct MenuItem {
+    pub entry: MenuEntry,
+    pub label: &'static str,
+    pub value: Option<String>,
+}
+
+impl MenuItem {
+    pub fn link(entry: MenuEntry, label: &'static str) -> MenuItem {
+        MenuItem {
+            entry,
+            label,
+            value: None,
+        }
+    }
+
+    pub fn option(entry: MenuEntry, label: &'static str, value: impl ToString) -> MenuItem {
+        MenuItem {
+            entry,
+            label,
+            value: Some(value.to_string()),
+        }
+    }
+
+    fn text(&self, focused: bool) -> String {
+        match &self.value {
+            Some(value) if focused => format!("{}:  < {} >", self.label, value),
+            Some(value) => format!("{}:  {}", self.label, value),
+            None => self.label.to_string(),
+        }
+    }
+}
+
+// Which page of the main menu is open and which of its rows has focus
+pub struct Menu {
+    pub page: MenuPage,
+    pub focus: usize,
+}
+
+impl Menu {
+    pub fn new() -> Menu {
+        Menu {
+            page: MenuPage::Main,
+            focus: 0,
+        }
+    }
+
+    pub fn open(&mut self, page: MenuPage) {
+        self.page = page;
+        self.focus = 0;
+    }
+
+    // Moves the focus up or down a row, wrapping around at either end
+    pub fn move_focus(&mut self, step: isize, count: usize) {
+        let count = count.max(1) as isize;
+        self.focus = (self.focus as isize + step).rem_euclid(count) as usize;
+    }
+}
+
+// The rows of a menu as drawn, one under the other with the whole list centred on
+// `center`. Each row is as wide as its text measures, which is also where the mouse
+// hits it.
+pub fn layout(
+    ctx: &Context,
+    items: &[MenuItem],
+    focus: usize,
+    scale: f32,
+    center: mint::Point2<f32>,
+) -> GameResult<Vec<(Text, Rect)>> {
+    let lines: Vec<String> = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| item.text(i == focus))
+        .collect();
+    let mut rows = stack(ctx, &lines, MENU_FONT_SIZE * scale, center.x, 0.0)?;
+    let height = rows.last().map_or(0.0, |(_, last)| last.bottom());
+    let top = center.y - height / 2.0;
+    for (_, rect) in &mut rows {
+        rect.y += top;
+    }
+    Ok(rows)
+}
+
+// Lines of text at `size` one under the other from `top`, each centred on `center_x`
+pub fn stack(
+    ctx: &Context,
+    lines: &[impl AsRef<str>],
+    size: f32,
+    center_x: f32,
+    top: f32,
+) -> GameResult<Vec<(Text, Rect)>> {
+    let mut rows = Vec::with_capacity(lines.len());
+    let mut y = top;
+    for line in lines {
+        let mut text = Text::new(line.as_ref());
+        text.set_scale(PxScale::from(size));
+        let measured = text.measure(ctx)?;
+        rows.push((
+            text,
+            Rect::new(center_x - measured.x / 2.0, y, measured.x, measured.y),
+        ));
+        y += measured.y * MENU_ROW_SPACING;
+    }
+    Ok(rows)
+}
+
+// The row under the mouse, if any
+pub fn hit(rows: &[(Text, Rect)], x: f32, y: f32) -> Option<usize> {
+    rows.iter()
+        .position(|(_, rect)| rect.contains(mint::Point2 { x, y }))
+}
+
+// The value `step` places after `current` in `all`, wrapping around
+pub fn cycle<T: Copy + PartialEq>(all: &[T], current: T, step: isize) -> T {
+    let index = all.iter().position(|value| *value == current).unwrap_or(0) as isize;
+    all[(index + step).rem_euclid(all.len() as isize) as usize]
+}
@@ -0,0 +1,348 @@
+use crate::constants::*;
+use crate::level::Level;
+use crate::scores::now_timestamp;
+use crate::simulation::{Simulation, Snake};
+use crate::storage;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Everything needed to play a run again: the rules it was started with and every turn
+// any snake made. The simulation only depends on those, so feeding the turns back on
+// the same ticks plays the run out exactly as it happened.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Replay {
+    pub timestamp: u64, // Seconds since the unix epoch when the run started
+    pub play_mode: PlayMode,
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub wall_rule: WallRule,
+    pub level: String, // The board as Level::to_text writes it, so later edits don't matter
+    pub snake_count: usize,
+    pub respawn_delay: Option<f32>,
+    pub pellet_lifetime: Option<f32>,
+    pub ticks: u32,    // How many ticks the run lasted
+    pub duration: f32, // Simulated seconds, see Simulation::elapsed
+    pub score: i32,
+    pub inputs: Vec<(u32, usize, Direction)>, // Tick, snake and the turn it made
+}
+
+impl Replay {
+    // An empty recording of the run `sim` is about to play on `level`
+    pub fn start(sim: &Simulation, level: &Level, play_mode: PlayMode) -> Replay {
+        Replay {
+            timestamp: now_timestamp(),
+            play_mode,
+            seed: sim.seed,
+            difficulty: sim.difficulty,
+            wall_rule: sim.wall_rule,
+            level: level.to_text(),
+            snake_count: sim.snakes.len(),
+            respawn_delay: sim.respawn_delay,
+            pellet_lifetime: sim.pellet_lifetime,
+            ticks: 0,
+            duration: 0.0,
+            score: 0,
+            inputs: Vec::new(),
+        }
+    }
+
+    // Notes the moves about to be fed to `sim` for the next tick. Only real turns are
+    // kept, a snake carrying on straight or trying to reverse changes nothing.
+    pub fn record(&mut self, sim: &Simulation, inputs: &[Option<Direction>]) {
+        for (index, (snake, input)) in sim.snakes.iter().zip(inputs).enumerate() {
+            if let Some(direction) = *input {
+                if snake.alive
+                    && direction != snake.direction
+                    && direction != snake.direction.opposite()
+                {
+                    self.inputs.push((self.ticks, index, direction));
+                }
+            }
+        }
+        self.ticks += 1;
+    }
+
+    // A fresh simulation set up exactly like the recorded one
+    pub fn simulation(&self) -> Result<Simulation, String> {
+        let level = Level::parse(&self.level)?;
+        let mut sim = Simulation::new(
+            self.difficulty,
+            self.wall_rule,
+            &level,
+            self.snake_count,
+            self.seed,
+        );
+        sim.respawn_delay = self.respawn_delay;
+        sim.pellet_lifetime = self.pellet_lifetime;
+        Ok(sim)
+    }
+
+    // Keeps this run as the best of its category and seed unless the stored one scored
+    // at least as much
+    pub fn save_if_best(&self, path: &Path) {
+        let best: Option<Replay> = storage::load_json(path);
+        if best.is_some_and(|best| best.score >= self.score) {
+            return;
+        }
+        if let Some(directory) = path.parent() {
+            if let Err(e) = fs::create_dir_all(directory) {
+                eprintln!("Failed to create replay directory {:?}: {}", directory, e);
+                return;
+            }
+        }
+        storage::save_json_compact(path, self);
+    }
+
+    // Saves the replay under the time it was saved in milliseconds, so the names sort
+    // oldest first, and drops the oldest beyond MAX_REPLAYS. Restarting the same seed can
+    // save twice within a millisecond, the later one then takes the next free name.
+    pub fn save(&self, directory: &Path) {
+        if let Err(e) = fs::create_dir_all(directory) {
+            eprintln!("Failed to create replay directory {:?}: {}", directory, e);
+            return;
+        }
+        let mut saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis());
+        let mut path = directory.join(format!("{}-{}.json", saved_at, self.seed));
+        while path.exists() {
+            saved_at += 1;
+            path = directory.join(format!("{}-{}.json", saved_at, self.seed));
+        }
+        storage::save_json_compact(&path, self);
+
+        let mut paths = replay_paths(directory);
+        if paths.len() > MAX_REPLAYS {
+            paths.truncate(paths.len() - MAX_REPLAYS);
+            for path in paths {
+                if let Err(e) = fs::remove_file(&path) {
+                    eprintln!("Failed to remove old replay {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+}
+
+// Where the best run of a score category on one seed is kept. These live apart from the
+// recent replays so pruning those never loses a best run.
+pub fn best_path(directory: &Path, category: &str, seed: u64) -> PathBuf {
+    let name: String = category
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    directory.join(format!("{}-{}.json", name, seed))
+}
+
+// A saved replay with what the Replays screen shows about it
+pub struct ReplayFile {
+    pub path: PathBuf,
+    pub replay: Replay,
+    pub level_name: String,
+}
+
+// Every readable replay in a directory, newest first
+pub fn load_replays(directory: &Path) -> Vec<ReplayFile> {
+    let mut replays: Vec<ReplayFile> = replay_paths(directory)
+        .into_iter()
+        .filter_map(|path| {
+            let replay: Replay = storage::load_json(&path)?;
+            match Level::parse(&replay.level) {
+                Ok(level) => Some(ReplayFile {
+                    path,
+                    level_name: level.name,
+                    replay,
+                }),
+                Err(e) => {
+                    eprintln!("Skipping replay {:?}: {}", path, e);
+                    None
+                }
+            }
+        })
+        .collect();
+    replays.reverse();
+    replays
+}
+
+// Replay files sorted oldest first, their names start with the time they were made
+fn replay_paths(directory: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Vec::new(); // Nothing recorded yet
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+// A replay being watched
+pub struct Playback {
+    pub replay: Replay,
+    pub tick: u32,     // Ticks played so far
+    next_input: usize, // First recorded turn not played yet
+    pub speed: usize,  // Index into REPLAY_SPEEDS
+    pub paused: bool,
+    pub finished: bool, // The recorded run is over, the last frame stays up
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Playback {
+        Playback {
+            replay,
+            tick: 0,
+            next_input: 0,
+            speed: 1,
+            paused: false,
+            finished: false,
+        }
+    }
+
+    // The recorded moves of the next tick, one per snake
+    pub fn next_inputs(&mut self, snake_count: usize) -> Vec<Option<Direction>> {
+        let mut inputs = vec![None; snake_count];
+        while let Some(&(tick, snake, direction)) = self.replay.inputs.get(self.next_input) {
+            if tick != self.tick {
+                break;
+            }
+            if let Some(input) = inputs.get_mut(snake) {
+                *input = Some(direction);
+            }
+            self.next_input += 1;
+        }
+        self.tick += 1;
+        inputs
+    }
+}
+
+// A best run played back next to a live one for the player to race. It has a simulation
+// of its own, so it never touches the live snake or its food.
+pub struct Ghost {
+    sim: Simulation,
+    playback: Playback,
+    last_update: f32, // Same tick clock as the live game, see GameState::last_update
+}
+
+impl Ghost {
+    pub fn new(replay: Replay) -> Option<Ghost> {
+        match replay.simulation() {
+            Ok(sim) => Some(Ghost {
+                sim,
+                playback: Playback::new(replay),
+                last_update: 0.0,
+            }),
+            Err(e) => {
+                eprintln!("Failed to load ghost: {}", e);
+                None
+            }
+        }
+    }
+
+    // Ticks on its own clock, so the ghost speeds up where the best run did
+    pub fn update(&mut self, delta: f32) {
+        self.last_update += delta;
+        if self.last_update >= self.sim.move_time && !self.finished() {
+            self.last_update = 0.0;
+            let inputs = self.playback.next_inputs(self.sim.snakes.len());
+            self.sim.step(&inputs);
+        }
+    }
+
+    // The recorded run is over, by dying or by reaching its last recorded tick
+    pub fn finished(&self) -> bool {
+        !self.sim.snakes[0].alive || self.playback.tick >= self.playback.replay.ticks
+    }
+
+    pub fn snake(&self) -> &Snake {
+        &self.sim.snakes[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{autopilot, choose_direction, AiSkill};
+
+    // A seeded run against two AI rivals, recorded the way GameState records one
+    fn record_run(seed: u64) -> (Replay, Simulation) {
+        let level = Level::open();
+        let mut sim = Simulation::new(Difficulty::Special, WallRule::Wrap, &level, 3, seed);
+        sim.pellet_lifetime = Some(PELLET_LIFETIME);
+        let mut replay = Replay::start(&sim, &level, PlayMode::Rivals);
+        while sim.running && replay.ticks < 3000 {
+            let inputs: Vec<Option<Direction>> = (0..sim.snakes.len())
+                .map(|index| match index {
+                    0 => autopilot(&sim, 0),
+                    _ => choose_direction(&sim, index, AiSkill::Aggressive),
+                })
+                .collect();
+            replay.record(&sim, &inputs);
+            sim.step(&inputs);
+        }
+        replay.duration = sim.elapsed;
+        replay.score = sim.player().score;
+        (replay, sim)
+    }
+
+    // Feeds the recorded turns back into a fresh simulation
+    fn play_back(replay: &Replay) -> (Simulation, u32) {
+        let mut sim = replay.simulation().expect("the recorded level parses");
+        let mut playback = Playback::new(replay.clone());
+        while playback.tick < replay.ticks {
+            let inputs = playback.next_inputs(sim.snakes.len());
+            sim.step(&inputs);
+        }
+        (sim, playback.tick)
+    }
+
+    #[test]
+    fn playing_a_replay_back_ends_where_the_run_did() {
+        let (replay, recorded) = record_run(7);
+        assert!(replay.score > 0, "the run should score something");
+
+        let (played, tick) = play_back(&replay);
+
+        assert_eq!(tick, replay.ticks);
+        assert_eq!(played.player().score, replay.score);
+        assert_eq!(played.elapsed, recorded.elapsed);
+        assert_eq!(played.food.pos, recorded.food.pos);
+        for (played, recorded) in played.snakes.iter().zip(&recorded.snakes) {
+            assert_eq!(played.alive, recorded.alive);
+            assert_eq!(played.score, recorded.score);
+            assert_eq!(played.head(), recorded.head());
+        }
+    }
+
+    #[test]
+    fn a_replay_survives_a_json_round_trip() {
+        let (replay, _) = record_run(11);
+
+        let json = serde_json::to_string(&replay).unwrap();
+        let loaded: Replay = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+        assert_eq!(loaded.inputs, replay.inputs);
+        let (played, tick) = play_back(&loaded);
+        assert_eq!(tick, replay.ticks);
+        assert_eq!(played.player().score, replay.score);
+    }
+
+    #[test]
+    fn saving_the_same_run_twice_keeps_both_replays() {
+        let directory = std::env::temp_dir().join(format!("snake-replays-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let (replay, _) = record_run(5);
+
+        replay.save(&directory);
+        replay.save(&directory);
+
+        assert_eq!(load_replays(&directory).len(), 2);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,192 @@
+use crate::constants::*;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScoreEntry {
+    pub score: i32,
+    pub length: usize,
+    pub timestamp: u64, // Seconds since the unix epoch
+    pub duration: f32,  // Seconds survived
+    pub seed: u64,
+}
+
+// Top score tables keyed by category, e.g. a difficulty name
+#[derive(Serialize, Deserialize, Default)]
+pub struct ScoreBook {
+    pub tables: BTreeMap<String, Vec<ScoreEntry>>,
+}
+
+impl ScoreBook {
+    // Loads the scores file, migrating the old single number highscore.txt the first time
+    pub fn load(path: &Path, legacy_path: &Path) -> ScoreBook {
+        if path.exists() {
+            if let Some(book) = storage::load_json(path) {
+                return book;
+            }
+            // Move the unreadable file aside so the next save can't overwrite those scores
+            let backup = path.with_extension("json.bak");
+            match fs::rename(path, &backup) {
+                Ok(()) => eprintln!("Moved the unreadable scores file to {:?}", backup),
+                Err(e) => eprintln!("Failed to move the unreadable scores file aside: {}", e),
+            }
+            return ScoreBook::default();
+        }
+
+        let mut book = ScoreBook::default();
+        if let Some(score) = fs::read_to_string(legacy_path)
+            .ok()
+            .and_then(|contents| contents.trim().parse::<i32>().ok())
+        {
+            // The old file never said which difficulty the score came from
+            if score > 0 {
+                book.insert(
+                    LEGACY_SCORE_CATEGORY,
+                    ScoreEntry {
+                        score,
+                        length: 0,
+                        timestamp: now_timestamp(),
+                        duration: 0.0,
+                        seed: 0,
+                    },
+                );
+            }
+            book.save(path);
+        }
+        book
+    }
+
+    pub fn save(&self, path: &Path) {
+        storage::save_json(path, self);
+    }
+
+    pub fn best(&self, category: &str) -> i32 {
+        self.table(category).first().map_or(0, |entry| entry.score)
+    }
+
+    pub fn table(&self, category: &str) -> &[ScoreEntry] {
+        self.tables.get(category).map_or(&[], |table| table.as_slice())
+    }
+
+    // Adds a run to its table, returning its rank when it made the top list
+    pub fn insert(&mut self, category: &str, entry: ScoreEntry) -> Option<usize> {
+        let table = self.tables.entry(category.to_string()).or_default();
+        // Earlier runs keep their place on ties
+        let rank = table
+            .iter()
+            .position(|existing| entry.score > existing.score)
+            .unwrap_or(table.len());
+        if rank >= SCORE_TABLE_SIZE {
+            return None;
+        }
+        table.insert(rank, entry);
+        table.truncate(SCORE_TABLE_SIZE);
+        Some(rank)
+    }
+}
+
+pub fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+// Formats a unix timestamp as YYYY-MM-DD (UTC)
+pub fn format_date(timestamp: u64) -> String {
+    // Civil-from-days conversion, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // An empty directory of its own for each test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("snake-scores-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry(score: i32, seed: u64) -> ScoreEntry {
+        ScoreEntry {
+            score,
+            length: 1,
+            timestamp: 0,
+            duration: 0.0,
+            seed,
+        }
+    }
+
+    #[test]
+    fn tables_keep_the_top_scores_in_order() {
+        let mut book = ScoreBook::default();
+        for score in 1..=SCORE_TABLE_SIZE as i32 {
+            book.insert("Normal", entry(score, 0));
+        }
+
+        assert_eq!(book.insert("Normal", entry(5, 1)), Some(6));
+        assert_eq!(book.insert("Normal", entry(0, 0)), None);
+        assert_eq!(book.insert("Normal", entry(20, 0)), Some(0));
+
+        let table = book.table("Normal");
+        assert_eq!(table.len(), SCORE_TABLE_SIZE);
+        let scores: Vec<i32> = table.iter().map(|entry| entry.score).collect();
+        assert_eq!(scores, vec![20, 10, 9, 8, 7, 6, 5, 5, 4, 3]);
+        // The earlier 5 keeps the better place
+        assert_eq!(table[6].seed, 0);
+        assert_eq!(table[7].seed, 1);
+        assert_eq!(book.best("Normal"), 20);
+        assert_eq!(book.best("Hard"), 0);
+    }
+
+    #[test]
+    fn the_old_high_score_file_becomes_the_legacy_table() {
+        let dir = temp_dir("legacy");
+        let (path, legacy_path) = (dir.join("scores.json"), dir.join("highscore.txt"));
+        fs::write(&legacy_path, "42\n").unwrap();
+
+        let book = ScoreBook::load(&path, &legacy_path);
+
+        assert_eq!(book.best(LEGACY_SCORE_CATEGORY), 42);
+        let saved: ScoreBook = storage::load_json(&path).unwrap();
+        assert_eq!(saved.best(LEGACY_SCORE_CATEGORY), 42);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn an_unreadable_scores_file_is_moved_aside() {
+        let dir = temp_dir("unreadable");
+        let (path, legacy_path) = (dir.join("scores.json"), dir.join("highscore.txt"));
+        fs::write(&path, "{ not json").unwrap();
+
+        let book = ScoreBook::load(&path, &legacy_path);
+
+        assert!(book.tables.is_empty());
+        assert!(!path.exists());
+        let backup = fs::read_to_string(dir.join("scores.json.bak")).unwrap();
+        assert_eq!(backup, "{ not json");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
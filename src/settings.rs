@@ -0,0 +1,212 @@
+use crate::ai::AiSkill;
+use crate::constants::*;
+use crate::input::KeyBindings;
+use crate::storage;
+use crate::theme::Theme;
+use crate::types::*;
+use ggez::filesystem::Filesystem;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct WindowSettings {
+    pub width: f32, // Physical pixels of the windowed size
+    pub height: f32,
+    pub x: Option<i32>, // Outer position, None lets the OS place the window
+    pub y: Option<i32>,
+    pub fullscreen: bool,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        WindowSettings {
+            width: REFERENCE_WIDTH,
+            height: REFERENCE_HEIGHT,
+            x: None,
+            y: None,
+            fullscreen: false,
+        }
+    }
+}
+
+// Everything that survives a restart, stored as settings.json in the user data dir
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub muted: bool,
+    pub difficulty: Difficulty,
+    pub wall_rule: WallRule,
+    pub ai_count: usize, // Computer snakes joining a game against AI rivals
+    pub ai_skill: AiSkill,
+    pub ghost: bool, // Race the best recorded run of the same rules and seed
+    pub window: WindowSettings,
+    pub vsync: bool, // Only read at startup, see startup_vsync
+    pub theme: Theme,
+    pub grid_lines: bool,
+    pub show_fps: bool,
+    pub input_queue_length: usize,
+    pub key_bindings: KeyBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            master_volume: 1.0,
+            music_volume: INITIAL_MUSIC_VOLUME,
+            sfx_volume: INITIAL_MUSIC_VOLUME,
+            muted: false,
+            difficulty: Difficulty::Normal,
+            wall_rule: WallRule::Solid,
+            ai_count: 2,
+            ai_skill: AiSkill::Safe,
+            ghost: true,
+            window: WindowSettings::default(),
+            vsync: true,
+            theme: Theme::Classic,
+            grid_lines: false,
+            show_fps: false,
+            input_queue_length: INPUT_QUEUE_LENGTH,
+            key_bindings: KeyBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    // Reads each field on its own so one bad value only resets that value
+    pub fn load(path: &Path) -> Settings {
+        let defaults = Settings::default();
+        let Ok(contents) = fs::read_to_string(path) else {
+            return defaults;
+        };
+        let object = match serde_json::from_str::<Value>(&contents) {
+            Ok(Value::Object(object)) => object,
+            _ => {
+                eprintln!("Settings file {:?} is malformed, using defaults", path);
+                return defaults;
+            }
+        };
+
+        Settings {
+            master_volume: field(&object, "master_volume", defaults.master_volume).clamp(0.0, 1.0),
+            music_volume: field(&object, "music_volume", defaults.music_volume).clamp(0.0, 1.0),
+            sfx_volume: field(&object, "sfx_volume", defaults.sfx_volume).clamp(0.0, 1.0),
+            muted: field(&object, "muted", defaults.muted),
+            difficulty: field(&object, "difficulty", defaults.difficulty),
+            wall_rule: field(&object, "wall_rule", defaults.wall_rule),
+            ai_count: field(&object, "ai_count", defaults.ai_count).clamp(1, MAX_RIVALS),
+            ai_skill: field(&object, "ai_skill", defaults.ai_skill),
+            ghost: field(&object, "ghost", defaults.ghost),
+            window: field(&object, "window", defaults.window),
+            vsync: field(&object, "vsync", defaults.vsync),
+            theme: field(&object, "theme", defaults.theme),
+            grid_lines: field(&object, "grid_lines", defaults.grid_lines),
+            show_fps: field(&object, "show_fps", defaults.show_fps),
+            input_queue_length: field(&object, "input_queue_length", defaults.input_queue_length)
+                .clamp(1, MAX_INPUT_QUEUE_LENGTH),
+            key_bindings: field(&object, "key_bindings", defaults.key_bindings).with_defaults(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) {
+        storage::save_json(path, self);
+    }
+}
+
+// The vsync setting, read before the window is made. There is no Context yet to find the
+// data dir through, so this asks a Filesystem of its own where ggez will put it.
+pub fn startup_vsync() -> bool {
+    let default = Settings::default().vsync;
+    let fs = match Filesystem::new(GAME_ID, GAME_AUTHOR, "resources", "resources.zip") {
+        Ok(fs) => fs,
+        Err(e) => {
+            eprintln!("Failed to find the settings file: {}", e);
+            return default;
+        }
+    };
+    let path = fs.user_data_dir().join(DATA_DIR_NAME).join("settings.json");
+    // Settings::load reports problems with the file once the game is up
+    match storage::load_json(&path) {
+        Some(Value::Object(object)) => object
+            .get("vsync")
+            .and_then(Value::as_bool)
+            .unwrap_or(default),
+        _ => default,
+    }
+}
+
+fn field<T: DeserializeOwned>(object: &Map<String, Value>, name: &str, default: T) -> T {
+    let Some(value) = object.get(name) else {
+        eprintln!("Settings: \"{}\" is missing, using the default", name);
+        return default;
+    };
+    match serde_json::from_value(value.clone()) {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("Settings: \"{}\" is invalid ({}), using the default", name, e);
+            default
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Action;
+
+    #[test]
+    fn a_partly_invalid_file_keeps_its_valid_fields() {
+        let dir = std::env::temp_dir().join(format!("snake-settings-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        // An older file: no theme, grid lines, key bindings or queue length yet, and a few
+        // values that were edited by hand
+        let old = r#"{
+            "master_volume": 0.4,
+            "music_volume": "loud",
+            "sfx_volume": 3.0,
+            "muted": true,
+            "difficulty": "Hard",
+            "wall_rule": "Sideways",
+            "ai_count": 99,
+            "window": { "width": 800.0 },
+            "vsync": false
+        }"#;
+        fs::write(&path, old).unwrap();
+
+        let settings = Settings::load(&path);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let defaults = Settings::default();
+        assert_eq!(settings.master_volume, 0.4);
+        assert_eq!(settings.music_volume, defaults.music_volume);
+        assert_eq!(settings.sfx_volume, 1.0);
+        assert!(settings.muted);
+        assert_eq!(settings.difficulty, Difficulty::Hard);
+        assert_eq!(settings.wall_rule, defaults.wall_rule);
+        assert_eq!(settings.ai_count, MAX_RIVALS);
+        assert_eq!(settings.window.width, defaults.window.width);
+        assert!(!settings.vsync);
+        assert_eq!(settings.theme, defaults.theme);
+        assert_eq!(settings.input_queue_length, INPUT_QUEUE_LENGTH);
+        let confirm = settings.key_bindings.keys(Action::Confirm);
+        assert!(confirm == defaults.key_bindings.keys(Action::Confirm));
+    }
+
+    #[test]
+    fn a_file_that_is_not_an_object_gives_the_defaults() {
+        let dir = std::env::temp_dir().join(format!("snake-settings-bad-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        fs::write(&path, "[1, 2").unwrap();
+
+        let settings = Settings::load(&path);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(settings.difficulty, Settings::default().difficulty);
+        assert!(settings.vsync);
+    }
+}
@@ -0,0 +1,845 @@
+use crate::constants::*;
+use crate::level::{Goal, Level};
+use crate::types::*;
+use nalgebra as na;
+use rand::rngs::Xoshiro256PlusPlus;
+use rand::{RngExt, SeedableRng};
+
+// Everything that happened during a single tick, in the order it happened.
+// The caller decides what each event sounds and looks like.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum GameEvent {
+    AteFood(usize), // Index of the snake that ate, the food or a pellet
+    Died(usize, DeathCause),
+    SpeedChanged(f32),
+    BoardCleared,  // The snakes fill every cell, the run is won
+    TargetReached, // The level's target score was hit, the run is won
+    GoalComplete,
+    GoalFailed, // A timed goal ran out
+    Respawned(usize),
+}
+
+// One snake on the board, the head is the first segment
+pub struct Snake {
+    pub body: Vec<SnakeSegment>,
+    pub direction: Direction,
+    pub score: i32,
+    pub alive: bool,
+    pub food_eaten: u32,
+    pub respawn_timer: f32, // Seconds until a dead snake comes back, when respawns are on
+}
+
+impl Snake {
+    fn new(start: na::Point2<i32>, direction: Direction) -> Snake {
+        Snake {
+            body: vec![SnakeSegment { pos: start }],
+            direction,
+            score: 0,
+            alive: true,
+            food_eaten: 0,
+            respawn_timer: 0.0,
+        }
+    }
+
+    pub fn head(&self) -> na::Point2<i32> {
+        self.body[0].pos
+    }
+}
+
+// The rules of the game with no ggez, audio or rendering attached.
+// One call to `step` advances every living snake by exactly one cell.
+pub struct Simulation {
+    pub snakes: Vec<Snake>, // The first snake is player one, targets and goals are judged on it
+    pub food: Food,
+    pub pellets: Vec<Pellet>,
+    pub difficulty: Difficulty,
+    pub wall_rule: WallRule,
+    pub move_time: f32, // Seconds between ticks, shrinks in Special
+    pub running: bool,  // False once every snake is dead or the run was won
+    pub elapsed: f32,   // Simulated seconds survived, the sum of every tick's move_time
+    pub width: i32,     // Board size in cells
+    pub height: i32,
+    pub seed: u64,
+    pub target_score: Option<i32>,
+    pub goal: Option<Goal>,
+    // Seconds a dead snake sits out before it comes back, None keeps it dead
+    pub respawn_delay: Option<f32>,
+    // Seconds the body of a fallen snake stays on the board as pellets, None leaves none
+    pub pellet_lifetime: Option<f32>,
+    rng: Xoshiro256PlusPlus, // Portable generator so a seed replays the same food everywhere
+    occupied: Vec<Option<usize>>, // One entry per cell, row major, the snake lying there
+    walls: Vec<bool>,        // Same layout as occupied, true on the level's walls
+    food_spots: Vec<na::Point2<i32>>,
+    // Where each snake started, respawns aim for the same cell
+    spawns: Vec<(na::Point2<i32>, Direction)>,
+}
+
+impl Simulation {
+    pub fn new(
+        difficulty: Difficulty,
+        wall_rule: WallRule,
+        level: &Level,
+        snake_count: usize,
+        seed: u64,
+    ) -> Simulation {
+        let move_time = match difficulty {
+            Difficulty::Easy => EASY_MOVE_TIME,
+            Difficulty::Normal => NORMAL_MOVE_TIME,
+            Difficulty::Hard => HARD_MOVE_TIME,
+            Difficulty::Special => SPECIAL_START_MOVE_TIME,
+        };
+        let (width, height) = (level.width, level.height);
+        let mut sim = Simulation {
+            snakes: Vec::new(),
+            food: Food { pos: level.start },
+            pellets: Vec::new(),
+            difficulty,
+            wall_rule,
+            move_time,
+            running: true,
+            elapsed: 0.0,
+            width,
+            height,
+            seed,
+            target_score: level.target_score,
+            goal: level.goal,
+            respawn_delay: None,
+            pellet_lifetime: None,
+            rng: Xoshiro256PlusPlus::seed_from_u64(seed),
+            occupied: vec![None; (width * height) as usize],
+            walls: vec![false; (width * height) as usize],
+            food_spots: level.food_spots.clone(),
+            spawns: Vec::new(),
+        };
+        for &wall in &level.walls {
+            let index = sim.index(wall);
+            sim.walls[index] = true;
+        }
+
+        // Player one starts where the level says, the second snake on the mirrored cell
+        // heading the other way, so neither side has the better spot. Any others take the
+        // cells mirrored across just one axis.
+        for index in 0..snake_count {
+            let (mut start, mut direction) = (level.start, level.direction);
+            if index % 4 == 1 || index % 4 == 2 {
+                start.x = width - 1 - start.x;
+                if matches!(direction, Direction::Left | Direction::Right) {
+                    direction = direction.opposite();
+                }
+            }
+            if index % 4 == 1 || index % 4 == 3 {
+                start.y = height - 1 - start.y;
+                if matches!(direction, Direction::Up | Direction::Down) {
+                    direction = direction.opposite();
+                }
+            }
+            let Some(start) = sim.nearest_free_cell(start) else {
+                break; // No room left for another snake
+            };
+            sim.set_occupied(start, Some(index));
+            sim.snakes.push(Snake::new(start, direction));
+            sim.spawns.push((start, direction));
+        }
+
+        if let Some(pos) = sim.random_free_cell() {
+            sim.food.pos = pos;
+        }
+        sim
+    }
+
+    pub fn player(&self) -> &Snake {
+        &self.snakes[0]
+    }
+
+    // Advance one tick with one input per snake. A turn straight back into the neck
+    // is ignored. Every snake moves first and collisions are judged afterwards, so
+    // no snake gets an advantage from where it sits in the list.
+    pub fn step(&mut self, inputs: &[Option<Direction>]) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        if !self.running {
+            return events;
+        }
+
+        for (snake, input) in self.snakes.iter_mut().zip(inputs) {
+            if let Some(direction) = *input {
+                if snake.alive && direction != snake.direction.opposite() {
+                    snake.direction = direction;
+                }
+            }
+        }
+
+        self.elapsed += self.move_time;
+        let move_time = self.move_time;
+        self.pellets.retain_mut(|pellet| {
+            pellet.time_left -= move_time;
+            pellet.time_left > 0.0
+        });
+        self.respawn_waiting(&mut events);
+
+        // Move every living snake. The tail only leaves its cell when the snake isn't
+        // growing this tick; heads are placed on the grid once the collisions are known.
+        let mut eaters = Vec::new();
+        let mut pellet_eaters = Vec::new();
+        let old_heads: Vec<na::Point2<i32>> = self.snakes.iter().map(Snake::head).collect();
+        for index in 0..self.snakes.len() {
+            if !self.snakes[index].alive {
+                continue;
+            }
+            let mut head = self.snakes[index].head() + self.snakes[index].direction.delta();
+            if self.wall_rule == WallRule::Wrap {
+                head.x = head.x.rem_euclid(self.width);
+                head.y = head.y.rem_euclid(self.height);
+            }
+
+            let snake = &mut self.snakes[index];
+            let last_pos = snake.body.last().unwrap().pos;
+            for i in (1..snake.body.len()).rev() {
+                snake.body[i].pos = snake.body[i - 1].pos;
+            }
+            snake.body[0].pos = head;
+
+            if head == self.food.pos {
+                self.snakes[index].body.push(SnakeSegment { pos: last_pos });
+                eaters.push(index);
+            } else if self.pellets.iter().any(|pellet| pellet.pos == head) {
+                self.snakes[index].body.push(SnakeSegment { pos: last_pos });
+                pellet_eaters.push(index);
+            } else if self.occupied[self.index(last_pos)] == Some(index) {
+                self.set_occupied(last_pos, None);
+            }
+        }
+
+        // Judge every head against the board as it stands after the move
+        let mut deaths = Vec::new();
+        for (index, snake) in self.snakes.iter().enumerate() {
+            if !snake.alive {
+                continue;
+            }
+            let head = snake.head();
+            let cause = if self.is_outside(head) || self.is_wall(head) {
+                Some(DeathCause::Wall)
+            } else if let Some(owner) = self.occupied[self.index(head)] {
+                Some(if owner == index {
+                    DeathCause::SelfCollision
+                } else {
+                    DeathCause::Snake
+                })
+            } else if self.snakes.iter().enumerate().any(|(other, rival)| {
+                // Meeting on the same cell, or swapping cells, which a one segment
+                // snake could otherwise slip through
+                other != index
+                    && rival.alive
+                    && (rival.head() == head
+                        || (rival.head() == old_heads[index] && old_heads[other] == head))
+            }) {
+                Some(DeathCause::HeadOn)
+            } else {
+                None
+            };
+            if let Some(cause) = cause {
+                deaths.push((index, cause));
+            }
+        }
+
+        // The dead leave the board, the survivors' heads take their new cells
+        for &(index, cause) in &deaths {
+            self.snakes[index].alive = false;
+            self.snakes[index].respawn_timer = self.respawn_delay.unwrap_or(0.0);
+            self.clear_snake(index);
+            events.push(GameEvent::Died(index, cause));
+        }
+        for index in 0..self.snakes.len() {
+            if self.snakes[index].alive {
+                let head = self.snakes[index].head();
+                self.set_occupied(head, Some(index));
+            }
+        }
+        if self.pellet_lifetime.is_some() {
+            for &(index, _) in &deaths {
+                self.drop_pellets(index);
+            }
+        }
+        if self.snakes.iter().all(|snake| !snake.alive) {
+            self.running = false;
+            return events;
+        }
+
+        // Pellets only feed the snake, the food and the speed stay as they are
+        for index in pellet_eaters {
+            if !self.snakes[index].alive {
+                continue;
+            }
+            let head = self.snakes[index].head();
+            self.pellets.retain(|pellet| pellet.pos != head);
+            let snake = &mut self.snakes[index];
+            snake.score += 1;
+            snake.food_eaten += 1;
+            events.push(GameEvent::AteFood(index));
+        }
+
+        for index in eaters {
+            if !self.snakes[index].alive {
+                continue;
+            }
+            let snake = &mut self.snakes[index];
+            snake.score += 1;
+            snake.food_eaten += 1;
+            events.push(GameEvent::AteFood(index));
+
+            // If in Special difficulty, increase speed
+            if let Difficulty::Special = self.difficulty {
+                // Reduce the move time by the speed-up factor, but not below the minimum
+                self.move_time = (self.move_time * SPEED_UP_FACTOR).max(MIN_MOVE_TIME);
+                events.push(GameEvent::SpeedChanged(self.move_time));
+            }
+
+            // Place new food on a free cell, or on a pellet once only pellets are left. The
+            // run ends when there is neither.
+            match self.random_free_cell() {
+                Some(pos) => self.food.pos = pos,
+                None if !self.pellets.is_empty() => {
+                    let nth = self.rng.random_range(0..self.pellets.len());
+                    self.food.pos = self.pellets.remove(nth).pos;
+                }
+                None => {
+                    self.snakes[index].score += BOARD_CLEAR_BONUS;
+                    self.running = false;
+                    events.push(GameEvent::BoardCleared);
+                    return events;
+                }
+            }
+        }
+
+        if !self.player().alive {
+            return events; // Targets and goals belong to player one
+        }
+        if self
+            .target_score
+            .is_some_and(|target| self.player().score >= target)
+        {
+            self.running = false;
+            events.push(GameEvent::TargetReached);
+            return events;
+        }
+
+        // Goals are judged after the move, so dying on the last tick still counts as dying
+        if let Some(goal) = self.goal {
+            let player = self.player();
+            let (complete, failed) = match goal {
+                Goal::Length(length) => (player.body.len() >= length, false),
+                Goal::FoodWithin { food, seconds } => {
+                    (player.food_eaten >= food, self.elapsed >= seconds)
+                }
+                Goal::Survive(seconds) => (self.elapsed >= seconds, false),
+            };
+            if complete {
+                self.running = false;
+                events.push(GameEvent::GoalComplete);
+            } else if failed {
+                self.running = false;
+                events.push(GameEvent::GoalFailed);
+            }
+        }
+
+        events
+    }
+
+    // Turns the body of a fallen snake into pellets, on every cell nothing else holds
+    fn drop_pellets(&mut self, snake: usize) {
+        let time_left = self.pellet_lifetime.unwrap_or(0.0);
+        for index in 0..self.snakes[snake].body.len() {
+            let pos = self.snakes[snake].body[index].pos;
+            if !self.is_outside(pos) && self.is_free(pos) && pos != self.food.pos {
+                self.pellets.push(Pellet { pos, time_left });
+            }
+        }
+    }
+
+    // Counts down the snakes sitting out a death and puts them back as a single segment
+    // on the free cell nearest to where they started. Score and food eaten carry over.
+    fn respawn_waiting(&mut self, events: &mut Vec<GameEvent>) {
+        if self.respawn_delay.is_none() {
+            return;
+        }
+        for index in 0..self.snakes.len() {
+            if self.snakes[index].alive {
+                continue;
+            }
+            self.snakes[index].respawn_timer -= self.move_time;
+            if self.snakes[index].respawn_timer > 0.0 {
+                continue;
+            }
+            let (spawn, direction) = self.spawns[index];
+            let Some(start) = self.nearest_free_cell(spawn) else {
+                continue; // Try again next tick
+            };
+            let snake = &mut self.snakes[index];
+            snake.body = vec![SnakeSegment { pos: start }];
+            snake.direction = direction;
+            snake.alive = true;
+            snake.respawn_timer = 0.0;
+            self.set_occupied(start, Some(index));
+            events.push(GameEvent::Respawned(index));
+        }
+    }
+
+    pub fn is_outside(&self, pos: na::Point2<i32>) -> bool {
+        pos.x < 0 || pos.y < 0 || pos.x >= self.width || pos.y >= self.height
+    }
+
+    pub fn is_occupied(&self, pos: na::Point2<i32>) -> bool {
+        self.occupied[self.index(pos)].is_some()
+    }
+
+    pub fn is_wall(&self, pos: na::Point2<i32>) -> bool {
+        self.walls[self.index(pos)]
+    }
+
+    // Every wall cell, for drawing
+    pub fn wall_cells(&self) -> impl Iterator<Item = na::Point2<i32>> + '_ {
+        self.walls
+            .iter()
+            .enumerate()
+            .filter(|(_, &wall)| wall)
+            .map(|(index, _)| self.point(index))
+    }
+
+    fn set_occupied(&mut self, pos: na::Point2<i32>, value: Option<usize>) {
+        let index = self.index(pos);
+        self.occupied[index] = value;
+    }
+
+    // Frees every cell a dead snake still claims
+    fn clear_snake(&mut self, snake: usize) {
+        for cell in self.occupied.iter_mut() {
+            if *cell == Some(snake) {
+                *cell = None;
+            }
+        }
+    }
+
+    fn index(&self, pos: na::Point2<i32>) -> usize {
+        (pos.y * self.width + pos.x) as usize
+    }
+
+    fn point(&self, index: usize) -> na::Point2<i32> {
+        na::Point2::new(index as i32 % self.width, index as i32 / self.width)
+    }
+
+    fn is_free(&self, pos: na::Point2<i32>) -> bool {
+        !self.is_occupied(pos)
+            && !self.is_wall(pos)
+            && !self.pellets.iter().any(|pellet| pellet.pos == pos)
+    }
+
+    // The free cell closest to `target`, searching outwards ring by ring
+    fn nearest_free_cell(&self, target: na::Point2<i32>) -> Option<na::Point2<i32>> {
+        let target = na::Point2::new(
+            target.x.clamp(0, self.width - 1),
+            target.y.clamp(0, self.height - 1),
+        );
+        (0..self.width.max(self.height)).find_map(|radius| {
+            (-radius..=radius)
+                .flat_map(|dy| (-radius..=radius).map(move |dx| na::Vector2::new(dx, dy)))
+                .filter(|offset| offset.x.abs().max(offset.y.abs()) == radius)
+                .map(|offset| target + offset)
+                .find(|&cell| !self.is_outside(cell) && self.is_free(cell))
+        })
+    }
+
+    // Picks uniformly among the level's free food spots when it has any, otherwise among
+    // every cell that is neither snake, wall nor pellet. None when the board is full.
+    fn random_free_cell(&mut self) -> Option<na::Point2<i32>> {
+        let free_spots: Vec<na::Point2<i32>> = self
+            .food_spots
+            .iter()
+            .copied()
+            .filter(|&spot| self.is_free(spot))
+            .collect();
+        if !free_spots.is_empty() {
+            let nth = self.rng.random_range(0..free_spots.len());
+            return Some(free_spots[nth]);
+        }
+
+        let free_cells: Vec<na::Point2<i32>> = (0..self.occupied.len())
+            .map(|index| self.point(index))
+            .filter(|&cell| self.is_free(cell))
+            .collect();
+        if free_cells.is_empty() {
+            return None;
+        }
+        let nth = self.rng.random_range(0..free_cells.len());
+        Some(free_cells[nth])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::autopilot;
+
+    fn cell(x: i32, y: i32) -> na::Point2<i32> {
+        na::Point2::new(x, y)
+    }
+
+    // A run on the open board with every snake laid out by hand, heads first. The food
+    // starts in the far corner, out of the way unless a test moves it.
+    fn sim_with(snakes: &[(&[(i32, i32)], Direction)]) -> Simulation {
+        let level = Level::open();
+        let mut sim = Simulation::new(Difficulty::Normal, WallRule::Solid, &level, snakes.len(), 1);
+        sim.occupied.fill(None);
+        for (index, (cells, direction)) in snakes.iter().enumerate() {
+            sim.snakes[index].body = cells
+                .iter()
+                .map(|&(x, y)| SnakeSegment { pos: cell(x, y) })
+                .collect();
+            sim.snakes[index].direction = *direction;
+            for &(x, y) in cells.iter() {
+                sim.set_occupied(cell(x, y), Some(index));
+            }
+        }
+        sim.food.pos = cell(GRID_WIDTH - 1, GRID_HEIGHT - 1);
+        sim
+    }
+
+    #[test]
+    fn eating_grows_the_snake_and_moves_the_food() {
+        let mut sim = sim_with(&[(&[(5, 5), (4, 5)], Direction::Right)]);
+        sim.food.pos = cell(6, 5);
+
+        let events = sim.step(&[None]);
+
+        assert_eq!(events, vec![GameEvent::AteFood(0)]);
+        let snake = sim.player();
+        assert_eq!(snake.head(), cell(6, 5));
+        assert_eq!(snake.body.len(), 3);
+        assert_eq!(snake.score, 1);
+        assert!(sim.food.pos != cell(6, 5));
+        assert!(!sim.is_occupied(sim.food.pos));
+    }
+
+    #[test]
+    fn hitting_the_edge_kills_the_snake() {
+        let mut sim = sim_with(&[(&[(GRID_WIDTH - 1, 5)], Direction::Right)]);
+
+        let events = sim.step(&[None]);
+
+        assert_eq!(events, vec![GameEvent::Died(0, DeathCause::Wall)]);
+        assert!(!sim.player().alive);
+        assert!(!sim.running);
+    }
+
+    #[test]
+    fn turning_into_the_body_kills_the_snake() {
+        let body = [(5, 5), (6, 5), (6, 6), (5, 6), (4, 6), (4, 5), (3, 5)];
+        let mut sim = sim_with(&[(&body, Direction::Left)]);
+
+        let events = sim.step(&[Some(Direction::Down)]);
+
+        assert_eq!(events, vec![GameEvent::Died(0, DeathCause::SelfCollision)]);
+        assert!(!sim.running);
+    }
+
+    #[test]
+    fn the_head_can_follow_into_the_cell_the_tail_leaves() {
+        let body = [(5, 5), (6, 5), (6, 6), (5, 6)];
+        let mut sim = sim_with(&[(&body, Direction::Left)]);
+
+        let events = sim.step(&[Some(Direction::Down)]);
+
+        assert!(events.is_empty());
+        assert!(sim.player().alive);
+        let body: Vec<_> = sim
+            .player()
+            .body
+            .iter()
+            .map(|segment| segment.pos)
+            .collect();
+        assert_eq!(body, vec![cell(5, 6), cell(5, 5), cell(6, 5), cell(6, 6)]);
+        assert!(sim.is_occupied(cell(5, 6)));
+    }
+
+    #[test]
+    fn the_same_seed_places_the_same_food() {
+        let food_positions = |seed: u64| {
+            let level = Level::open();
+            let mut sim = Simulation::new(Difficulty::Normal, WallRule::Solid, &level, 1, seed);
+            let mut positions = vec![sim.food.pos];
+            for _ in 0..2000 {
+                let input = autopilot(&sim, 0);
+                if sim.step(&[input]).contains(&GameEvent::AteFood(0)) {
+                    positions.push(sim.food.pos);
+                }
+            }
+            positions
+        };
+
+        let first = food_positions(42);
+        assert!(first.len() > 10, "the autopilot should eat along the way");
+        assert_eq!(first, food_positions(42));
+    }
+
+    #[test]
+    fn snakes_meeting_head_on_both_die() {
+        let mut sim = sim_with(&[
+            (&[(5, 5), (4, 5)], Direction::Right),
+            (&[(7, 5), (8, 5)], Direction::Left),
+        ]);
+
+        let events = sim.step(&[None, None]);
+
+        assert_eq!(
+            events,
+            vec![
+                GameEvent::Died(0, DeathCause::HeadOn),
+                GameEvent::Died(1, DeathCause::HeadOn),
+            ]
+        );
+    }
+
+    #[test]
+    fn snakes_swapping_cells_both_die() {
+        let mut sim = sim_with(&[(&[(5, 5)], Direction::Right), (&[(6, 5)], Direction::Left)]);
+
+        let events = sim.step(&[None, None]);
+
+        assert_eq!(
+            events,
+            vec![
+                GameEvent::Died(0, DeathCause::HeadOn),
+                GameEvent::Died(1, DeathCause::HeadOn),
+            ]
+        );
+    }
+
+    #[test]
+    fn snakes_dying_on_the_same_tick_end_the_run_as_a_draw() {
+        // The first runs into the second's body while the second runs off the edge
+        let mut sim = sim_with(&[
+            (&[(GRID_WIDTH - 2, 4)], Direction::Down),
+            (
+                &[
+                    (GRID_WIDTH - 1, 5),
+                    (GRID_WIDTH - 2, 5),
+                    (GRID_WIDTH - 3, 5),
+                ],
+                Direction::Right,
+            ),
+        ]);
+
+        let events = sim.step(&[None, None]);
+
+        assert_eq!(
+            events,
+            vec![
+                GameEvent::Died(0, DeathCause::Snake),
+                GameEvent::Died(1, DeathCause::Wall),
+            ]
+        );
+        assert!(sim.snakes.iter().all(|snake| !snake.alive));
+        assert!(!sim.running);
+    }
+
+    #[test]
+    fn a_head_can_take_the_cell_a_rival_tail_leaves() {
+        // Whichever snake moves first, the tail is gone before heads are judged
+        let chaser: &[(i32, i32)] = &[(5, 5)];
+        let leaver: &[(i32, i32)] = &[(7, 6), (7, 5), (6, 5)];
+        for leaver_first in [false, true] {
+            let mut snakes = vec![(chaser, Direction::Right), (leaver, Direction::Down)];
+            if leaver_first {
+                snakes.reverse();
+            }
+            let mut sim = sim_with(&snakes);
+            let chaser_index = usize::from(leaver_first);
+
+            let events = sim.step(&[None, None]);
+
+            assert!(events.is_empty());
+            assert!(sim.snakes.iter().all(|snake| snake.alive));
+            assert_eq!(sim.snakes[chaser_index].head(), cell(6, 5));
+        }
+    }
+
+    #[test]
+    fn a_level_with_room_for_one_snake_places_only_one() {
+        let level = Level::parse("name: Cell\nsize: 5x5\n---\n#####\n#S###\n#####\n#####\n#####\n")
+            .unwrap();
+
+        let sim = Simulation::new(Difficulty::Normal, WallRule::Solid, &level, MAX_PLAYERS, 1);
+
+        assert_eq!(sim.snakes.len(), 1);
+    }
+
+    #[test]
+    fn food_lands_on_a_pellet_once_no_other_cell_is_free() {
+        let level =
+            Level::parse("name: Pocket\nsize: 5x5\n---\n#####\n#S..#\n#####\n#####\n#####\n")
+                .unwrap();
+        let mut sim = Simulation::new(Difficulty::Normal, WallRule::Solid, &level, 1, 1);
+        sim.food.pos = cell(2, 1);
+        sim.pellets.push(Pellet {
+            pos: cell(3, 1),
+            time_left: PELLET_LIFETIME,
+        });
+
+        let events = sim.step(&[None]);
+
+        assert_eq!(events, vec![GameEvent::AteFood(0)]);
+        assert!(sim.running);
+        assert_eq!(sim.food.pos, cell(3, 1));
+        assert!(sim.pellets.is_empty());
+    }
+
+    #[test]
+    fn eating_the_last_free_cell_clears_the_board() {
+        let level =
+            Level::parse("name: Pocket\nsize: 5x5\n---\n#####\n#S.##\n#####\n#####\n#####\n")
+                .unwrap();
+        let mut sim = Simulation::new(Difficulty::Normal, WallRule::Solid, &level, 1, 1);
+        assert_eq!(sim.food.pos, cell(2, 1));
+
+        let events = sim.step(&[None]);
+
+        assert_eq!(events, vec![GameEvent::AteFood(0), GameEvent::BoardCleared]);
+        assert_eq!(sim.player().score, 1 + BOARD_CLEAR_BONUS);
+        assert_eq!(sim.player().body.len(), 2);
+        assert!(!sim.running);
+    }
+
+    #[test]
+    fn wrap_carries_the_head_across_every_edge() {
+        let (right, bottom) = (GRID_WIDTH - 1, GRID_HEIGHT - 1);
+        let crossings = [
+            ((right, 5), Direction::Right, (0, 5)),
+            ((0, 5), Direction::Left, (right, 5)),
+            ((5, 0), Direction::Up, (5, bottom)),
+            ((5, bottom), Direction::Down, (5, 0)),
+        ];
+        for ((x, y), direction, (to_x, to_y)) in crossings {
+            let mut sim = sim_with(&[(&[(x, y)], direction)]);
+            sim.wall_rule = WallRule::Wrap;
+
+            let events = sim.step(&[None]);
+
+            assert!(events.is_empty(), "{:?} at ({}, {})", direction, x, y);
+            assert!(sim.player().alive);
+            assert_eq!(sim.player().head(), cell(to_x, to_y));
+            assert!(sim.is_occupied(cell(to_x, to_y)));
+        }
+    }
+
+    #[test]
+    fn wrap_still_stops_at_a_wall_on_the_far_edge() {
+        let level = Level::parse(
+            "name: Edge\nsize: 5x5\ndirection: Left\n---\n.....\nS...#\n.....\n.....\n.....\n",
+        )
+        .unwrap();
+        let mut sim = Simulation::new(Difficulty::Normal, WallRule::Wrap, &level, 1, 1);
+
+        let events = sim.step(&[None]);
+
+        assert_eq!(events, vec![GameEvent::Died(0, DeathCause::Wall)]);
+        assert_eq!(sim.player().head(), cell(4, 1));
+        assert!(!sim.running);
+    }
+
+    #[test]
+    fn reaching_the_length_goal_completes_it() {
+        let mut sim = sim_with(&[(&[(5, 5), (4, 5)], Direction::Right)]);
+        sim.goal = Some(Goal::Length(3));
+        sim.food.pos = cell(6, 5);
+
+        let events = sim.step(&[None]);
+
+        assert_eq!(events, vec![GameEvent::AteFood(0), GameEvent::GoalComplete]);
+        assert!(!sim.running);
+    }
+
+    #[test]
+    fn eating_enough_in_time_completes_the_food_goal() {
+        let mut sim = sim_with(&[(&[(5, 5)], Direction::Right)]);
+        sim.goal = Some(Goal::FoodWithin {
+            food: 1,
+            seconds: sim.move_time * 2.0,
+        });
+        sim.food.pos = cell(7, 5);
+
+        assert!(sim.step(&[None]).is_empty());
+        let events = sim.step(&[None]);
+
+        assert_eq!(events, vec![GameEvent::AteFood(0), GameEvent::GoalComplete]);
+        assert!(!sim.running);
+    }
+
+    #[test]
+    fn running_out_of_time_fails_the_food_goal() {
+        let mut sim = sim_with(&[(&[(5, 5)], Direction::Right)]);
+        sim.goal = Some(Goal::FoodWithin {
+            food: 1,
+            seconds: sim.move_time * 2.0,
+        });
+
+        assert!(sim.step(&[None]).is_empty());
+        let events = sim.step(&[None]);
+
+        assert_eq!(events, vec![GameEvent::GoalFailed]);
+        assert!(!sim.running);
+    }
+
+    #[test]
+    fn surviving_long_enough_completes_the_survive_goal() {
+        let mut sim = sim_with(&[(&[(5, 5)], Direction::Right)]);
+        sim.goal = Some(Goal::Survive(sim.move_time * 2.0));
+
+        assert!(sim.step(&[None]).is_empty());
+        let events = sim.step(&[None]);
+
+        assert_eq!(events, vec![GameEvent::GoalComplete]);
+    }
+
+    #[test]
+    fn dying_on_the_last_tick_does_not_complete_the_goal() {
+        let mut sim = sim_with(&[(&[(GRID_WIDTH - 1, 5)], Direction::Right)]);
+        sim.goal = Some(Goal::Survive(sim.move_time));
+
+        let events = sim.step(&[None]);
+
+        assert_eq!(events, vec![GameEvent::Died(0, DeathCause::Wall)]);
+    }
+
+    #[test]
+    fn a_fallen_snake_leaves_pellets_and_respawns_after_the_delay() {
+        let edge = GRID_WIDTH - 1;
+        let mut sim = sim_with(&[
+            (&[(5, 5)], Direction::Down),
+            (
+                &[(edge, 10), (edge - 1, 10), (edge - 2, 10)],
+                Direction::Right,
+            ),
+        ]);
+        sim.respawn_delay = Some(sim.move_time * 2.0);
+        sim.pellet_lifetime = Some(PELLET_LIFETIME);
+        // Its start is now covered by its own pellets, so it has to come back beside them
+        sim.spawns[1] = (cell(edge - 1, 10), Direction::Up);
+
+        let events = sim.step(&[None, None]);
+
+        assert_eq!(events, vec![GameEvent::Died(1, DeathCause::Wall)]);
+        assert!(sim.running);
+        let pellets: Vec<_> = sim.pellets.iter().map(|pellet| pellet.pos).collect();
+        assert_eq!(pellets, vec![cell(edge, 10), cell(edge - 1, 10)]);
+
+        assert!(sim.step(&[None, None]).is_empty());
+        assert!(!sim.snakes[1].alive);
+
+        let events = sim.step(&[None, None]);
+
+        assert_eq!(events, vec![GameEvent::Respawned(1)]);
+        let snake = &sim.snakes[1];
+        assert!(snake.alive);
+        assert_eq!(snake.body.len(), 1);
+        assert!(!pellets.contains(&snake.head()));
+        assert_eq!(sim.pellets.len(), 2);
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs;
+use std::path::Path;
+
+// Reads a JSON file, None when it doesn't exist or can't be parsed
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("Failed to parse {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+// Writes to a temporary file first and renames it over the target,
+// so a crash mid-write never leaves a half written file behind
+pub fn save_json<T: Serialize>(path: &Path, value: &T) {
+    write_atomic(path, serde_json::to_string_pretty(value));
+}
+
+// Same as save_json on one line, for files that are big and never edited by hand
+pub fn save_json_compact<T: Serialize>(path: &Path, value: &T) {
+    write_atomic(path, serde_json::to_string(value));
+}
+
+fn write_atomic(path: &Path, contents: serde_json::Result<String>) {
+    let contents = match contents {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("Failed to serialize {:?}: {}", path, e);
+            return;
+        }
+    };
+    let temp_path = path.with_extension("tmp");
+    if let Err(e) = fs::write(&temp_path, contents) {
+        eprintln!("Failed to write {:?}: {}", temp_path, e);
+        return;
+    }
+    if let Err(e) = fs::rename(&temp_path, path) {
+        eprintln!("Failed to replace {:?}: {}", path, e);
+    }
+}
@@ -0,0 +1,81 @@
+use ggez::graphics::Color;
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Theme {
+    Classic,
+    Neon,
+    Mono,
+}
+
+// Colors the board is drawn with
+pub struct Palette {
+    pub background: Color,
+    pub snake: Color,
+    pub food: Color,
+    pub border: Color,
+    pub wall: Color,        // Obstacles inside a level
+    pub rivals: [Color; 3], // Every snake after the first, in turn
+}
+
+impl Palette {
+    pub fn snake_color(&self, index: usize) -> Color {
+        match index {
+            0 => self.snake,
+            _ => self.rivals[(index - 1) % self.rivals.len()],
+        }
+    }
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Classic, Theme::Neon, Theme::Mono];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Classic => "Classic",
+            Theme::Neon => "Neon",
+            Theme::Mono => "Mono",
+        }
+    }
+
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Classic => Palette {
+                background: Color::BLACK,
+                snake: Color::from_rgb(50, 150, 50),
+                food: Color::from_rgb(255, 0, 0),
+                border: Color::from_rgb(255, 0, 0),
+                wall: Color::from_rgb(130, 130, 130),
+                rivals: [
+                    Color::from_rgb(60, 120, 255),
+                    Color::from_rgb(255, 150, 0),
+                    Color::from_rgb(200, 80, 255),
+                ],
+            },
+            Theme::Neon => Palette {
+                background: Color::from_rgb(10, 0, 25),
+                snake: Color::from_rgb(0, 255, 200),
+                food: Color::from_rgb(255, 0, 180),
+                border: Color::from_rgb(140, 0, 255),
+                wall: Color::from_rgb(90, 40, 200),
+                rivals: [
+                    Color::from_rgb(255, 230, 0),
+                    Color::from_rgb(0, 150, 255),
+                    Color::from_rgb(255, 100, 0),
+                ],
+            },
+            Theme::Mono => Palette {
+                background: Color::from_rgb(20, 20, 20),
+                snake: Color::from_rgb(220, 220, 220),
+                food: Color::WHITE,
+                border: Color::from_rgb(120, 120, 120),
+                wall: Color::from_rgb(90, 90, 90),
+                rivals: [
+                    Color::from_rgb(140, 140, 140),
+                    Color::from_rgb(180, 180, 180),
+                    Color::from_rgb(100, 100, 100),
+                ],
+            },
+        }
+    }
+}
@@ -1,12 +1,45 @@
 use nalgebra as na;
+use serde::{Deserialize, Serialize};
 
 #[derive(PartialEq, Clone, Copy)]
 pub enum GameMode {
     Menu,
     Playing,
+    Paused,
+    GameOver,
+    HighScores,
+    LevelSelect,
+    Campaign,
+    LevelComplete,
+    Editor,
+    RoundOver,
+    MatchOver,
+    Replays,
+    ReplayPlayback,
+    Controls,
 }
 
-#[derive(PartialEq, Clone, Copy)]
+// Who shares the board
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum PlayMode {
+    Solo,
+    Versus, // Two players on one keyboard, WASD against the arrows
+    Coop,   // The same two players on one team, sharing the food and the score
+    Rivals, // One player against computer snakes
+}
+
+impl PlayMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            PlayMode::Solo => "Solo",
+            PlayMode::Versus => "Versus",
+            PlayMode::Coop => "Co-op",
+            PlayMode::Rivals => "vs AI",
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum Difficulty {
     Easy,
     Normal,
@@ -14,12 +47,143 @@ pub enum Difficulty {
     Special,
 }
 
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Special,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Special => "Special",
+        }
+    }
+}
+
+// What happens when the snake reaches the edge of the board
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum WallRule {
+    Solid, // Hitting the edge ends the run
+    Wrap,  // Leaving one side re-enters from the opposite side
+}
+
+impl WallRule {
+    pub fn name(self) -> &'static str {
+        match self {
+            WallRule::Solid => "Solid",
+            WallRule::Wrap => "Wrap",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum DeathCause {
+    Wall,
+    SelfCollision,
+    Snake,  // Ran into another snake's body
+    HeadOn, // Met another snake head first
+}
+
+// How a run came to an end
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum RunOutcome {
+    Died(DeathCause),
+    BoardCleared,
+    TargetReached,
+    GoalComplete,
+    OutOfTime, // A timed goal ran out before it was met
+}
+
+impl RunOutcome {
+    pub fn is_win(self) -> bool {
+        !matches!(self, RunOutcome::Died(_) | RunOutcome::OutOfTime)
+    }
+}
+
+// Everything the game over screen reports about a finished run
+#[derive(Clone)]
+pub struct RunSummary {
+    pub score: i32,
+    pub length: usize,
+    pub difficulty: Difficulty,
+    pub wall_rule: WallRule,
+    pub time_survived: f32, // Real seconds played, see GameState::run_time
+    pub food_eaten: u32,
+    pub final_move_time: f32,
+    pub outcome: RunOutcome,
+    pub level_name: String,
+    pub new_high_score: bool, // A new best score, or a new best star rating in the campaign
+    pub stars: u8,            // Earned by completing a goal, 0 otherwise
+    // Place among every snake on the board and how many there were, against AI rivals
+    pub rank: Option<(usize, usize)>,
+    pub seed: u64,
+}
+
+impl RunSummary {
+    pub fn food_per_minute(&self) -> f32 {
+        if self.time_survived > 0.0 {
+            self.food_eaten as f32 * 60.0 / self.time_survived
+        } else {
+            0.0
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Food {
-    pub pos: na::Point2<f32>,
+    pub pos: na::Point2<i32>,
+}
+
+// Food left where a snake fell, gone again after a while
+#[derive(Clone, Copy)]
+pub struct Pellet {
+    pub pos: na::Point2<i32>,
+    pub time_left: f32, // Seconds before it disappears
 }
 
 #[derive(Clone, Copy, PartialEq)]
 pub struct SnakeSegment {
-    pub pos: na::Point2<f32>,
-} 
\ No newline at end of file
+    pub pos: na::Point2<i32>,
+}
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Up => "Up",
+            Direction::Down => "Down",
+            Direction::Left => "Left",
+            Direction::Right => "Right",
+        }
+    }
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    // One cell step in this direction, with y growing downwards.
+    pub fn delta(self) -> na::Vector2<i32> {
+        match self {
+            Direction::Up => na::Vector2::new(0, -1),
+            Direction::Down => na::Vector2::new(0, 1),
+            Direction::Left => na::Vector2::new(-1, 0),
+            Direction::Right => na::Vector2::new(1, 0),
+        }
+    }
+}